@@ -2,10 +2,18 @@ if __name__ == "__main__":
     print("Please run as unittest, for example: python -m unittest pytest.py")
     exit()
 
+import asyncio
+import math
+import os
+import socket
+import struct
+import threading
+import tempfile
 import unittest
 from time import time_ns
 
 from rocketsim import *
+from rocketsim import field, rewards, rlbot, state_setters
 from rocketsim.sim import *
 
 
@@ -15,6 +23,325 @@ class TestArena(unittest.TestCase):
         arena = Arena(GameMode.Soccar, 120)
         self.assertAlmostEqual(arena.get_tick_rate(), 120.0, 2)
 
+    def test_init(self):
+        self.assertTrue(is_initialized())
+
+        info = init()
+        self.assertGreater(info.meshes_loaded["soccar"], 0)
+        self.assertEqual(info.is_complete, len(info.missing) == 0)
+
+        # the meshes can only be loaded once, so the original result is returned
+        self.assertEqual(init("not_a_folder").folder, info.folder)
+
+    def test_raycast(self):
+        arena = Arena(GameMode.Soccar, 120)
+
+        hit = arena.raycast(Vec3(0, 0, 500), Vec3(0, 0, -1), 1000)
+        self.assertIsNotNone(hit)
+        self.assertAlmostEqual(hit.distance, 500, delta=1)
+        self.assertAlmostEqual(hit.pos.z, 0, delta=1)
+        self.assertAlmostEqual(hit.normal.z, 1, 2)
+
+        hit = arena.raycast(Vec3(0, 0, 500), Vec3(0, 0, 3), 5000)
+        self.assertIsNotNone(hit)
+        self.assertAlmostEqual(hit.pos.z, 2048, delta=10)
+        self.assertLess(hit.normal.z, 0)
+
+        self.assertIsNone(arena.raycast(Vec3(0, 0, 500), Vec3(0, 0, -1), 100))
+        self.assertIsNone(Arena(GameMode.TheVoid).raycast(Vec3(0, 0, 500), Vec3(0, 0, -1), 1000))
+
+        with self.assertRaises(ValueError):
+            arena.raycast(Vec3(0, 0, 500), Vec3(0, 0, 0), 1000)
+
+    def test_raycast_batch(self):
+        import numpy as np
+
+        arena = Arena(GameMode.Soccar, 120)
+        origins = np.array([[0, 0, 500], [0, 0, 500]], dtype=np.float32)
+        directions = np.array([[0, 0, -1], [0, 0, 1]], dtype=np.float32)
+
+        hits = arena.raycast_batch(origins, directions, 1000)
+        self.assertEqual(hits.shape, (2, 7))
+        self.assertAlmostEqual(hits[0, 6], 500, delta=1)
+        self.assertTrue(np.isnan(hits[1]).all())
+
+    def test_car_obb(self):
+        arena = Arena(GameMode.Soccar, 120)
+        config = CarConfig.octane()
+        car_id = arena.add_car(Team.Blue, config)
+
+        car = arena.get_car(car_id)
+        car.pos = Vec3(100, 200, 300)
+        arena.set_car(car_id, car)
+
+        obb = arena.get_car_obb(car_id)
+        self.assertEqual(len(obb.corners), 8)
+        self.assertAlmostEqual(obb.half_extents.x, config.hitbox_size.x / 2, 4)
+        for axis in "xyz":
+            mean = sum(getattr(corner, axis) for corner in obb.corners) / 8
+            self.assertAlmostEqual(mean, getattr(obb.center, axis), 3)
+
+        car = arena.get_car(car_id)
+        for corner, car_corner in zip(obb.corners, car.hitbox_corners(config)):
+            self.assertAlmostEqual(corner.x, car_corner.x, 3)
+            self.assertAlmostEqual(corner.y, car_corner.y, 3)
+            self.assertAlmostEqual(corner.z, car_corner.z, 3)
+
+        self.assertRaises(IndexError, arena.get_car_obb, car_id + 1)
+
+    def test_car_obbs_batch(self):
+        import numpy as np
+
+        arena = Arena(GameMode.Soccar, 120)
+        self.assertEqual(arena.get_car_obbs().shape, (0, 8, 3))
+
+        arena.add_car(Team.Blue, CarConfig.octane())
+        arena.add_car(Team.Orange, CarConfig.merc())
+        obbs = arena.get_car_obbs()
+        self.assertEqual(obbs.shape, (2, 8, 3))
+
+        for i, car_id in enumerate(arena.get_cars()):
+            corners = arena.get_car_obb(car_id).corners
+            self.assertTrue(np.allclose(obbs[i], [[c.x, c.y, c.z] for c in corners], atol=1e-3))
+
+    def test_step_record(self):
+        import numpy as np
+
+        arena = Arena(GameMode.Soccar, 120)
+        blue = arena.add_car(Team.Blue, CarConfig.octane())
+        orange = arena.add_car(Team.Orange, CarConfig.octane())
+        arena.set_car_controls(blue, CarControls(throttle=1))
+
+        trajectory = arena.step_record(25, every_n_ticks=10)
+        self.assertEqual(trajectory.shape, (2, 3, 15))
+        self.assertEqual(arena.get_tick_count(), 25)
+
+        trajectory = arena.step_record(10, fields=["pos", "ang_vel"], car_ids=[orange])
+        self.assertEqual(trajectory.shape, (10, 2, 6))
+        ball, car = arena.get_ball(), arena.get_car(orange)
+        self.assertTrue(np.allclose(trajectory[-1, 0, :3], [ball.pos.x, ball.pos.y, ball.pos.z]))
+        self.assertTrue(np.allclose(trajectory[-1, 1, 3:], [car.ang_vel.x, car.ang_vel.y, car.ang_vel.z]))
+
+        with self.assertRaises(ValueError):
+            arena.step_record(10, fields=["boost"])
+        with self.assertRaises(IndexError):
+            arena.step_record(10, car_ids=[orange + 1])
+        # more ticks than RocketSim can step at once
+        with self.assertRaises(ValueError):
+            arena.step_record(2**31)
+        self.assertEqual(arena.get_tick_count(), 35)
+
+    def test_field(self):
+        arena = Arena(GameMode.Soccar, 120)
+        soccar = field.get_field(GameMode.Soccar)
+
+        self.assertEqual(len(soccar.boost_pads), arena.num_pads())
+        for i, pad in enumerate(soccar.boost_pads):
+            arena_pad = arena.get_pad_static(i)
+            self.assertEqual(pad.is_big, arena_pad.is_big)
+            self.assertEqual(pad.pos.x, arena_pad.pos.x)
+            self.assertEqual(pad.pos.y, arena_pad.pos.y)
+            self.assertEqual(pad.pos.z, arena_pad.pos.z)
+
+        self.assertLess(field.own_goal(Team.Blue).center.y, 0)
+        self.assertGreater(field.opponent_goal(Team.Blue).center.y, 0)
+        self.assertTrue(field.is_in_goal(Vec3(0, -5300, 100), Team.Blue))
+        self.assertFalse(field.is_in_goal(Vec3(0, -5300, 100), Team.Orange))
+        self.assertFalse(field.is_in_goal(Vec3(0, 0, 100), Team.Blue))
+
+        self.assertAlmostEqual(field.distance_to_wall(Vec3(0, 0, 17)), 4096)
+        self.assertAlmostEqual(field.distance_to_wall(Vec3(4000, 0, 17)), 96)
+        self.assertLess(field.distance_to_wall(Vec3(4000, 4100, 17)), 0)
+
+        self.assertEqual(len(field.get_field(GameMode.TheVoid).boost_pads), 0)
+        self.assertIsNone(field.own_goal(Team.Blue, GameMode.TheVoid))
+
+    def test_state_setters(self):
+        def make_arena(gamemode=GameMode.Soccar):
+            arena = Arena(gamemode, 120)
+            for team in (Team.Blue, Team.Blue, Team.Blue, Team.Orange, Team.Orange, Team.Orange):
+                arena.add_car(team, CarConfig.octane())
+            return arena
+
+        arenas = [make_arena() for _ in range(2)]
+        states = [state_setters.random_state(arena, seed=42) for arena in arenas]
+        self.assertEqual(states[0], states[1])
+        self.assertNotEqual(state_setters.random_state(arenas[1], seed=43), states[0])
+
+        for seed in range(20):
+            game_state = state_setters.random_state(arenas[0], seed=seed)
+            self.assertGreaterEqual(field.distance_to_wall(game_state.ball.pos), 91)
+            for car in game_state.cars:
+                self.assertGreaterEqual(field.distance_to_wall(car.state.pos), 100)
+                self.assertTrue(0 <= car.state.boost <= 100)
+                if car.state.is_on_ground:
+                    self.assertAlmostEqual(car.state.pos.z, 17)
+
+            positions = [game_state.ball.pos] + [car.state.pos for car in game_state.cars]
+            for i, pos in enumerate(positions):
+                for other in positions[i + 1 :]:
+                    self.assertGreater(math.dist((pos.x, pos.y, pos.z), (other.x, other.y, other.z)), 150)
+
+        game_state = state_setters.random_boost(arenas[0], seed=1, min_boost=30, max_boost=40)
+        self.assertTrue(all(30 <= car.state.boost <= 40 for car in game_state.cars))
+        with self.assertRaises(ValueError):
+            state_setters.random_boost(arenas[0], min_boost=50, max_boost=10)
+
+        game_state = state_setters.goalie(arenas[0], Team.Orange, seed=3)
+        self.assertGreater(game_state.ball.pos.y, 0)
+        self.assertGreater(game_state.ball.vel.y, 0)
+        goalie = next(car for car in game_state.cars if car.team == Team.Orange)
+        self.assertTrue(goalie.state.pos.approx_eq(Vec3(0, 4970, 17)))
+
+        with self.assertRaises(ValueError):
+            state_setters.goalie(make_arena(GameMode.TheVoid))
+        for setter in (state_setters.random_state, state_setters.random_ball, state_setters.random_cars):
+            with self.assertRaises(ValueError):
+                setter(make_arena(GameMode.TheVoid))
+
+        game_state = state_setters.kickoff(arenas[0], seed=5)
+        self.assertEqual(game_state.ball.pos.x, 0)
+        self.assertEqual(game_state.ball.pos.y, 0)
+
+    def test_rewards(self):
+        import numpy as np
+
+        arena = Arena(GameMode.Soccar, 120)
+        blue = arena.add_car(Team.Blue, CarConfig.octane())
+        orange = arena.add_car(Team.Orange, CarConfig.octane())
+
+        reward_fn = rewards.RewardFunction([(rewards.RewardTerm.Goal, 10), (rewards.RewardTerm.BoostUsage, -1), (rewards.RewardTerm.FacingBall, 0.5)])
+        reward_fn.reset(arena)
+
+        ball = arena.get_ball()
+        ball.pos = Vec3(0., -5119., 184.)
+        ball.vel = ball.vel.with_y(-6600)
+        arena.set_ball(ball)
+        arena.set_car_boost(blue, 100)
+        arena.set_car_boost(orange, 100)
+        reward_fn.reset(arena)
+
+        arena.set_car_controls(blue, CarControls(boost=True))
+        arena.step(10)
+        car_rewards = reward_fn.compute(arena)
+        self.assertEqual(car_rewards.shape, (2,))
+
+        terms = reward_fn.last_terms
+        self.assertEqual(terms.shape, (2, 3))
+        self.assertEqual(list(terms[:, 0]), [-1, 1])
+        self.assertGreater(terms[0, 1], 0)
+        self.assertEqual(terms[1, 1], 0)
+        self.assertTrue(np.allclose(car_rewards, terms @ np.array([10, -1, 0.5])))
+
+        with self.assertRaises(ValueError):
+            rewards.RewardFunction([])
+
+    def test_rlbot_packet(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Orange, CarConfig.octane())
+        car = arena.get_car(car_id)
+        car.pos = Vec3(500, -1000, 17)
+        car.boost = 50
+        car.has_jumped = True
+        arena.set_car(car_id, car)
+
+        game_state = arena.get_game_state()
+        packet = rlbot.game_state_to_packet(game_state)
+        field_info = rlbot.get_field_info()
+
+        self.assertEqual(packet["num_cars"], 1)
+        self.assertEqual(packet["game_cars"][0]["team"], 1)
+        self.assertAlmostEqual(packet["game_cars"][0]["boost"], game_state.cars[0].state.boost)
+        self.assertTrue(packet["game_cars"][0]["jumped"])
+        self.assertEqual(packet["num_boosts"], field_info["num_boosts"])
+
+        for pad in packet["game_boosts"]:
+            self.assertTrue(pad["is_active"])
+
+        # RLBot's FieldInfo order, which isn't quite sorted by y then x
+        rlbot_pads = [
+            (0, -4240), (-1792, -4184), (1792, -4184), (-3072, -4096), (3072, -4096), (-940, -3308), (940, -3308),
+            (0, -2816), (-3584, -2484), (3584, -2484), (-1788, -2300), (1788, -2300), (-2048, -1036), (0, -1024),
+            (2048, -1036), (-3584, 0), (-1024, 0), (1024, 0), (3584, 0), (-2048, 1036), (0, 1024), (2048, 1036),
+            (-1788, 2300), (1788, 2300), (-3584, 2484), (3584, 2484), (0, 2816), (-940, 3310), (940, 3308),
+            (-3072, 4096), (3072, 4096), (-1792, 4184), (1792, 4184), (0, 4240),
+        ]
+        self.assertEqual([(pad["location"]["x"], pad["location"]["y"]) for pad in field_info["boost_pads"]], rlbot_pads)
+        big_pads = [i for i, pad in enumerate(field_info["boost_pads"]) if pad["is_full_boost"]]
+        self.assertEqual(big_pads, [3, 4, 15, 18, 29, 30])
+
+        packet["game_ball"]["physics"]["location"]["z"] = 500
+        packet["game_cars"][0]["physics"]["location"]["x"] = -500
+        packet["game_cars"][0]["physics"]["rotation"]["yaw"] = 1.5
+        packet["game_boosts"][13]["is_active"] = False
+        packet["game_boosts"][13]["timer"] = 1
+
+        new_state = rlbot.packet_to_game_state(packet, game_state)
+        self.assertEqual(new_state.ball.pos.z, 500)
+        self.assertEqual(new_state.cars[0].state.pos.x, -500)
+        self.assertTrue(new_state.cars[0].state.has_jumped)
+        inactive = [pad.position for pad in new_state.pads if not pad.state.is_active]
+        self.assertEqual([(pad.x, pad.y) for pad in inactive], [(0, -1024)])
+
+        round_trip = rlbot.game_state_to_packet(new_state)
+        self.assertAlmostEqual(round_trip["game_cars"][0]["physics"]["rotation"]["yaw"], 1.5, 4)
+        self.assertFalse(round_trip["game_boosts"][13]["is_active"])
+        self.assertAlmostEqual(round_trip["game_boosts"][13]["timer"], 1)
+
+        packet["num_cars"] = 2
+        self.assertRaises(ValueError, rlbot.packet_to_game_state, packet, game_state)
+
+    def test_car_presets(self):
+        self.assertEqual(len(CarConfig.all_presets()), 6)
+        for preset, config in CarConfig.all_presets():
+            self.assertEqual(config.preset, preset)
+            self.assertEqual(CarConfig.from_name(preset.name).preset, preset)
+
+        self.assertEqual(CarConfig.from_name(" Dominus ").preset, CarPreset.Dominus)
+        self.assertEqual(CarPreset.from_name("MERC"), CarPreset.Merc)
+        self.assertEqual(CarConfig.from_preset(CarPreset.Plank).hitbox_size.x, CarConfig.plank().hitbox_size.x)
+        self.assertRaises(ValueError, CarConfig.from_name, "batmobile")
+
+        config = CarConfig.octane()
+        config.hitbox_size.x += 10
+        self.assertIsNone(config.preset)
+
+    def test_car_config_validation(self):
+        for _, config in CarConfig.all_presets():
+            config.validate()
+            self.assertGreater(config.wheelbase, 0)
+            self.assertGreater(config.track_width, 0)
+            front_width = abs(config.front_wheels.connection_point_offset.y) * 2
+            back_width = abs(config.back_wheels.connection_point_offset.y) * 2
+            self.assertAlmostEqual(config.track_width, (front_width + back_width) / 2, 4)
+
+        octane = CarConfig.octane()
+        corners = octane.hitbox_corners
+        self.assertEqual(len(corners), 8)
+        self.assertAlmostEqual(max(c.x for c in corners) - min(c.x for c in corners), octane.hitbox_size.x, 4)
+        self.assertAlmostEqual(sum(c.z for c in corners) / 8, octane.hitbox_pos_offset.z, 4)
+
+        self.assertRaises(ValueError, CarConfig, Vec3(-1, 2, 3))
+        self.assertRaises(ValueError, CarConfig, Vec3(1, 2, 3), dodge_deadzone=2)
+        self.assertRaises(ValueError, CarConfig, Vec3(1, 2, 3), front_wheels=WheelPairConfig(0, 10))
+
+        # the whole config is validated on construction and by every setter
+        self.assertEqual(CarConfig(), CarConfig.octane())
+        with self.assertRaises(ValueError):
+            octane.back_wheels.suspension_rest_length = 500
+        with self.assertRaises(ValueError):
+            octane.hitbox_size = Vec3(0, 1, 1)
+        with self.assertRaises(ValueError):
+            octane.dodge_deadzone = -1
+        self.assertEqual(octane, CarConfig.octane())
+
+        # configs that didn't come from the constructor are still checked when a car is added
+        arena = Arena(GameMode.Soccar, 120)
+        zero_config = CarInfo(0).config
+        self.assertRaises(ValueError, zero_config.validate)
+        self.assertRaises(ValueError, arena.add_car, Team.Blue, zero_config)
+        self.assertEqual(arena.num_cars(), 0)
+
     def test_boost_pad(self):
         arena = Arena(GameMode.Soccar, 120)
         pad = arena.get_pad_static(0)
@@ -34,6 +361,40 @@ class TestArena(unittest.TestCase):
         self.assertEqual(ball2.pos.y, ball.pos.y)
         self.assertEqual(ball2.pos.z, 1500)
 
+    def test_nested_values(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+        game_state = arena.get_game_state()
+
+        game_state.cars[0].state.rot_mat.up.z = 0.5
+        game_state.cars[0].state.last_controls.boost = True
+        game_state.pads[0].state.is_active = False
+        self.assertEqual(game_state.cars[0].state.rot_mat.up.z, 0.5)
+        self.assertTrue(game_state.cars[0].state.last_controls.boost)
+        self.assertFalse(game_state.pads[0].state.is_active)
+
+        pos = game_state.ball.pos
+        pos.x = 100
+        self.assertEqual(game_state.ball.pos.x, 100)
+        self.assertEqual(game_state.ball.pos, Vec3(100, 0, pos.z))
+        self.assertRaises(TypeError, setattr, pos, "y", "far")
+
+        # a handle read before its owner was changed elsewhere only writes the field that's set
+        ball = game_state.ball
+        pos = ball.pos
+        ball.pos = Vec3(1, 2, 3)
+        pos.x = 10
+        self.assertEqual(ball.pos, Vec3(10, 2, 3))
+        self.assertEqual(pos, Vec3(10, 2, 3))
+        stale_up = game_state.cars[0].state.rot_mat.up
+        game_state.cars[0].state.rot_mat.up.x = 0.25
+        stale_up.y = 0.75
+        self.assertEqual(game_state.cars[0].state.rot_mat.up, Vec3(0.25, 0.75, 0.5))
+
+        self.assertRaises(AttributeError, setattr, game_state.pads[0].position, "z", 0)
+        self.assertRaises(AttributeError, setattr, arena.get_pad_static(0).pos, "z", 0)
+        self.assertRaises(AttributeError, setattr, arena.get_car(car_id).pos, "w", 0)
+
     def test_add_car(self):
         arena = Arena(GameMode.Soccar, 120)
         self.assertEqual(arena.num_cars(), 0)
@@ -65,6 +426,107 @@ class TestArena(unittest.TestCase):
 
         self.assertEqual(car1.boost, car.boost)
 
+    def test_views(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+        car_view = arena.car_view(car_id)
+        ball_view = arena.ball_view()
+
+        ball = arena.get_ball()
+        ball.pos = ball.pos.with_z(1500)
+        arena.set_ball(ball)
+        arena.step(10)
+
+        self.assertEqual(ball_view.pos, arena.get_ball().pos)
+        self.assertEqual(ball_view.get(), arena.get_ball())
+        self.assertEqual(car_view.pos, arena.get_car(car_id).pos)
+        self.assertEqual(car_view.get(), arena.get_car(car_id))
+        # each attribute is read on its own
+        car = arena.get_car(car_id)
+        self.assertEqual(car_view.rot_mat, car.rot_mat)
+        self.assertEqual(car_view.boost, car.boost)
+        self.assertEqual(car_view.ball_hit_info, car.ball_hit_info)
+        self.assertEqual(car_view.last_controls, car.last_controls)
+        self.assertEqual(ball_view.vel, arena.get_ball().vel)
+        self.assertEqual(car_view.id, car_id)
+        self.assertTrue(car_view.is_valid)
+
+        # the arena is borrowed while it steps, which doesn't make its views invalid
+        valid_in_callback = []
+        def callback(team):
+            valid_in_callback.append((car_view.is_valid, ball_view.is_valid))
+
+        arena.set_goal_scored_callback(callback)
+        ball = arena.get_ball()
+        ball.pos = Vec3(0., -5119., 184.)
+        ball.vel.y = -6600
+        arena.set_ball(ball)
+        arena.step(2)
+        self.assertEqual(valid_in_callback, [(True, True)])
+
+        with self.assertRaises(IndexError):
+            arena.car_view(car_id + 1)
+
+        arena.remove_car(car_id)
+        self.assertFalse(car_view.is_valid)
+        with self.assertRaises(IndexError):
+            car_view.pos
+
+        # views don't keep the arena alive
+        del arena
+        self.assertFalse(ball_view.is_valid)
+        with self.assertRaises(ReferenceError):
+            ball_view.pos
+
+    def test_car_placement(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+
+        arena.place_car_airborne(car_id, Vec3(0, 0, 1000), RotMat.identity(), vel=Vec3(0, 0, 500), has_flip=False)
+        car = arena.get_car(car_id)
+        self.assertEqual(car.pos, Vec3(0, 0, 1000))
+        self.assertEqual(car.vel, Vec3(0, 0, 500))
+        self.assertFalse(car.is_on_ground)
+        self.assertTrue(car.has_jumped)
+        self.assertTrue(car.has_double_jumped)
+
+        # placing a car leaves its demo state alone
+        car = arena.get_car(car_id)
+        car.is_demoed = True
+        car.demo_respawn_timer = 2
+        arena.set_car(car_id, car)
+
+        arena.place_car_on_ground(car_id, Vec3(1000, -2000, 500), yaw=1.5707963)
+        car = arena.get_car(car_id)
+        self.assertTrue(car.is_demoed)
+        self.assertEqual(car.demo_respawn_timer, 2)
+        # pos.z is ignored
+        self.assertTrue(car.pos.approx_eq(Vec3(1000, -2000, 17)))
+        self.assertTrue(car.rot_mat.approx_eq(RotMat.from_angles(0, 1.5707963, 0)))
+        self.assertEqual(car.vel, Vec3())
+        self.assertTrue(car.is_on_ground)
+        self.assertFalse(car.has_jumped)
+        self.assertFalse(car.has_double_jumped)
+        car.is_demoed = False
+        car.demo_respawn_timer = 0
+        arena.set_car(car_id, car)
+
+        arena.place_car_on_wall(car_id, Vec3(4096, 0, 500), Vec3(-1, 0, 0))
+        car = arena.get_car(car_id)
+        self.assertTrue(car.pos.approx_eq(Vec3(4079, 0, 500)))
+        self.assertTrue(car.rot_mat.approx_eq(RotMat(Vec3(0, 0, 1), Vec3(0, 1, 0), Vec3(-1, 0, 0))))
+        self.assertTrue(car.is_on_ground)
+
+        with self.assertRaises(ValueError):
+            arena.place_car_on_wall(car_id, Vec3(0, 0, 0), Vec3(0, 0, 1))
+
+        arena.set_car_boost(car_id, 12.5)
+        self.assertEqual(arena.get_car(car_id).boost, 12.5)
+        with self.assertRaises(ValueError):
+            arena.set_car_boost(car_id, 101)
+        with self.assertRaises(IndexError):
+            arena.set_car_boost(car_id + 1, 50)
+
     def test_car_controls(self):
         arena = Arena(GameMode.Soccar, 120)
         car_id = arena.add_car(Team.Blue, CarConfig.octane())
@@ -98,6 +560,461 @@ class TestArena(unittest.TestCase):
 
         print(f"Simulated {ticks / 120}s of game time in {(end_time - start_time) / 1e6}ms real time")
 
+    def test_state_hash(self):
+        arenas = [Arena(GameMode.Soccar, 120) for _ in range(3)]
+        for arena in arenas:
+            car_id = arena.add_car(Team.Blue, CarConfig.octane())
+            arena.set_car_controls(car_id, CarControls(throttle=1, boost=True))
+
+        source = arenas.pop()
+        source.step(30)
+        for arena in arenas:
+            arena.set_game_state(source.get_game_state())
+
+        self.assertEqual(source.state_hash(), arenas[0].state_hash())
+        self.assertEqual(arenas[0].state_hash(), arenas[1].state_hash())
+
+        for _ in range(60):
+            for arena in arenas:
+                arena.step()
+
+            self.assertEqual(arenas[0].state_hash(), arenas[1].state_hash())
+
+    def test_step_threads(self):
+        def make_arena():
+            arena = Arena(GameMode.Soccar, 120)
+            for team in (Team.Blue, Team.Orange):
+                car_id = arena.add_car(team, CarConfig.octane())
+                arena.set_car_controls(car_id, CarControls(throttle=1, boost=True))
+            return arena
+
+        arenas = [make_arena() for _ in range(4)]
+        threads = [threading.Thread(target=arena.step, args=(600,)) for arena in arenas]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        expected = make_arena()
+        expected.step(600)
+        for arena in arenas:
+            self.assertEqual(arena.state_hash(), expected.state_hash())
+
+    def test_step_async(self):
+        async def step_all(arenas):
+            return await asyncio.gather(*(arena.step_async(600) for arena in arenas))
+
+        arenas = [Arena(GameMode.Soccar, 120) for _ in range(4)]
+        for arena in arenas:
+            arena.add_car(Team.Blue, CarConfig.octane())
+
+        for arena, observation in zip(arenas, asyncio.run(step_all(arenas))):
+            self.assertEqual(arena.get_tick_count(), 600)
+            self.assertEqual(observation.shape, (2, 15))
+            ball, car = arena.get_ball(), arena.get_car(arena.get_cars()[0])
+            self.assertEqual(list(observation[0, :3]), [ball.pos.x, ball.pos.y, ball.pos.z])
+            self.assertEqual(list(observation[1, 3:6]), [car.vel.x, car.vel.y, car.vel.z])
+
+        release = threading.Event()
+
+        def block_on_goal(arena):
+            ball = arena.get_ball()
+            ball.pos = Vec3(0., -5119., 184.)
+            ball.vel.y = -6600
+            arena.set_ball(ball)
+            arena.set_goal_scored_callback(lambda team: release.wait(10))
+
+        # the arena is borrowed as soon as step_async returns, so it can't be used until the step is done
+        arena = arenas[0]
+        block_on_goal(arena)
+
+        async def step_while_busy():
+            future = arena.step_async(2, fields=["pos"], car_ids=[])
+            with self.assertRaises(RuntimeError):
+                arena.get_tick_count()
+            release.set()
+            return await future
+
+        self.assertEqual(asyncio.run(step_while_busy()).shape, (1, 3))
+        self.assertEqual(arena.get_tick_count(), 602)
+
+        # with every worker busy, the steps are queued instead of blocking the event loop
+        release.clear()
+        busy_arenas = [Arena(GameMode.Soccar, 120) for _ in range((os.cpu_count() or 1) + 2)]
+        for busy_arena in busy_arenas:
+            block_on_goal(busy_arena)
+
+        async def step_queued():
+            futures = [busy_arena.step_async(2, fields=["pos"], car_ids=[]) for busy_arena in busy_arenas]
+            self.assertFalse(any(future.done() for future in futures))
+            release.set()
+            return await asyncio.gather(*futures)
+
+        start = time_ns()
+        self.assertEqual(len(asyncio.run(step_queued())), len(busy_arenas))
+        self.assertLess(time_ns() - start, 5_000_000_000)
+
+        async def step_invalid():
+            arena.step_async(1, car_ids=[1000])
+
+        with self.assertRaises(IndexError):
+            asyncio.run(step_invalid())
+
+    def test_game_state_bytes(self):
+        arena = Arena(GameMode.Soccar, 120)
+        arena.add_car(Team.Blue, CarConfig.octane())
+
+        self.assertEqual(GameState.from_bytes(arena.get_game_state().to_bytes()), arena.get_game_state())
+        with self.assertRaises(ValueError):
+            GameState.from_bytes(b"")
+
+        # the team comes after the id of the only car, which is at the end
+        empty = Arena(GameMode.Soccar, 120).get_game_state().to_bytes()
+        corrupt = bytearray(arena.get_game_state().to_bytes())
+        corrupt[len(empty) + 4] = 7
+        with self.assertRaises(ValueError):
+            GameState.from_bytes(bytes(corrupt))
+
+    def test_game_state_diff(self):
+        arena = Arena(GameMode.Soccar, 120)
+        arena.add_car(Team.Blue, CarConfig.octane())
+        arena.add_car(Team.Orange, CarConfig.octane())
+
+        before = arena.get_game_state()
+        self.assertEqual(before.diff(arena.get_game_state()), [])
+
+        car = arena.get_car(before.cars[1].id)
+        car.boost = 10
+        arena.set_car(before.cars[1].id, car)
+
+        diffs = before.diff(arena.get_game_state())
+        self.assertEqual([diff.field for diff in diffs], ["cars[1].state.boost"])
+        self.assertEqual(diffs[0].new, 10)
+        self.assertEqual(str(diffs[0]), "cars[1].state.boost: 33.333332 -> 10")
+        self.assertEqual(before.diff(arena.get_game_state(), tolerance=100), [])
+
+        # ids are compared exactly, whatever the tolerance
+        other = arena.get_game_state()
+        other.cars[0].id += 1
+        self.assertEqual([diff.field for diff in before.diff(other, tolerance=100)], ["cars[0].id"])
+
+        other = arena.get_game_state()
+        other.ball_rot = RotMat.from_angles(0, 1, 0)
+        other.cars[0].config.hitbox_size.x += 1
+        other.pads[0].state.cur_locked_car_id = before.cars[0].id
+        fields = [diff.field for diff in before.diff(other)]
+        self.assertTrue(fields[0].startswith("ball_rot."))
+        self.assertIn("cars[0].config.hitbox_size.x", fields)
+        self.assertEqual(fields[-1], "pads[0].state.cur_locked_car_id")
+
+        self.assertTrue(Vec3(1, 2, 3).approx_eq(Vec3(1, 2, 3.00001)))
+        self.assertFalse(Vec3(1, 2, 3).approx_eq(Vec3(1, 2, 3.1)))
+        self.assertTrue(Vec3(1, 2, 3).approx_eq(Vec3(1, 2, 3.1), 0.2))
+        self.assertTrue(RotMat.identity().approx_eq(RotMat.from_angles(0, 0, 0)))
+        self.assertFalse(RotMat.identity().approx_eq(RotMat.from_angles(0, 1, 0)))
+        self.assertTrue(before.ball.approx_eq(arena.get_ball()))
+        self.assertFalse(before.cars[1].state.approx_eq(car))
+        self.assertTrue(car.approx_eq(arena.get_car(before.cars[1].id)))
+
+    def test_equality(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+
+        self.assertEqual(arena.get_game_state(), arena.get_game_state())
+        self.assertEqual(arena.get_car(car_id), arena.get_car(car_id))
+        self.assertEqual(arena.get_ball(), arena.get_ball())
+        self.assertEqual(CarConfig.octane(), CarConfig.octane())
+        self.assertNotEqual(CarConfig.octane(), CarConfig.dominus())
+        self.assertEqual(CarControls(throttle=1), CarControls(throttle=1))
+        self.assertNotEqual(CarControls(throttle=1), CarControls(steer=1))
+        self.assertEqual(RotMat.identity(), RotMat(Vec3(1, 0, 0), Vec3(0, 1, 0), Vec3(0, 0, 1)))
+        self.assertNotEqual(Vec3(1, 2, 3), (1, 2, 3))
+
+        car = arena.get_car(car_id)
+        car.boost = 10
+        self.assertNotEqual(car, arena.get_car(car_id))
+        state = arena.get_game_state()
+        state.cars[0].state.boost = 10
+        self.assertNotEqual(state, arena.get_game_state())
+
+        pads = {arena.get_pad_static(i) for i in range(arena.num_pads())}
+        self.assertEqual(len(pads), arena.num_pads())
+        self.assertIn(arena.get_pad_static(0), pads)
+        self.assertEqual(len(set(field.Field.soccar().goals)), 2)
+
+        with self.assertRaises(TypeError):
+            hash(arena.get_ball())
+
+    def test_serve(self):
+        with socket.socket() as probe:
+            probe.bind(("127.0.0.1", 0))
+            port = probe.getsockname()[1]
+
+        server = threading.Thread(target=serve, args=(port,), kwargs={"max_connections": 1})
+        server.start()
+
+        def request(client, command, body=b""):
+            payload = bytes([command]) + body
+            client.sendall(struct.pack("<I", len(payload)) + payload)
+            length = struct.unpack("<I", client.recv(4, socket.MSG_WAITALL))[0]
+            response = client.recv(length, socket.MSG_WAITALL)
+            return response[0], response[1:]
+
+        for _ in range(100):
+            try:
+                client = socket.create_connection(("127.0.0.1", port))
+                break
+            except ConnectionRefusedError:
+                threading.Event().wait(0.05)
+
+        with client:
+            status, body = request(client, 0, struct.pack("<Bf", 0, 120))
+            self.assertEqual(status, 0)
+            arena = struct.unpack("<I", body)[0]
+
+            status, body = request(client, 2, struct.pack("<IBB", arena, 0, 0))
+            self.assertEqual(status, 0)
+            car_id = struct.unpack("<I", body)[0]
+
+            status, _ = request(client, 4, struct.pack("<IIIfffffBBB", arena, 1, car_id, 1, 0, 0, 0, 0, 0, 1, 0))
+            self.assertEqual(status, 0)
+
+            status, start = request(client, 6, struct.pack("<I", arena))
+            self.assertEqual(status, 0)
+
+            status, stepped = request(client, 5, struct.pack("<II", arena, 60))
+            self.assertEqual(status, 0)
+            self.assertEqual(struct.unpack("<Q", stepped[:8])[0], 60)
+
+            status, _ = request(client, 7, struct.pack("<I", arena) + start)
+            self.assertEqual(status, 0)
+            _, reset = request(client, 6, struct.pack("<I", arena))
+            # tick count, then tick rate, pad count, car count and the ball position
+            self.assertEqual(reset[8:32], start[8:32])
+
+            # long steps are split into chunks, so the server can check for Ctrl+C in between
+            status, stepped = request(client, 5, struct.pack("<II", arena, 2500))
+            self.assertEqual(status, 0)
+            self.assertEqual(struct.unpack("<Q", stepped[:8])[0], struct.unpack("<Q", reset[:8])[0] + 2500)
+
+            status, message = request(client, 5, struct.pack("<II", arena + 1, 1))
+            self.assertEqual(status, 1)
+            self.assertIn(b"No arena", message)
+
+            # a car with an invalid team, which must be rejected without taking the server down
+            _, body = request(client, 0, struct.pack("<Bf", 0, 120))
+            _, empty = request(client, 6, body)
+            # the team comes after the id of the last car
+            corrupt = bytearray(start)
+            corrupt[len(empty) + 4] = 7
+            status, message = request(client, 7, struct.pack("<I", arena) + bytes(corrupt))
+            self.assertEqual(status, 1)
+            self.assertIn(b"invalid team", message)
+
+            status, _ = request(client, 6, struct.pack("<I", arena))
+            self.assertEqual(status, 0)
+
+        server.join(5)
+        self.assertFalse(server.is_alive())
+
+    def test_render_topdown(self):
+        arena = Arena(GameMode.Soccar, 120)
+        arena.add_car(Team.Blue, CarConfig.octane())
+        arena.add_car(Team.Orange, CarConfig.dominus())
+
+        svg = arena.render_topdown(300, 400)
+        self.assertTrue(svg.startswith("<svg"))
+        self.assertIn('width="300" height="400"', svg)
+        # both goals, the field outline and two cars
+        self.assertEqual(svg.count("<polygon"), 5)
+        # the boost pads and the ball
+        self.assertEqual(svg.count("<circle"), arena.num_pads() + 1)
+        self.assertRaises(ValueError, arena.render_topdown, 0, 400)
+
+        with tempfile.TemporaryDirectory() as folder:
+            path = os.path.join(folder, "episode.rsrp")
+            recorder = ArenaRecorder(path, every_n_ticks=5)
+            arena.attach_recorder(recorder)
+            arena.step(10)
+            recorder.close()
+
+            paths = ReplayReader(path).render_frames(os.path.join(folder, "frames"))
+            self.assertEqual(len(paths), 3)
+            with open(paths[-1]) as file:
+                self.assertTrue(file.read().startswith("<svg"))
+
+    def test_recorder(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+        arena.set_car_controls(car_id, CarControls(throttle=1))
+
+        ball = arena.get_ball()
+        ball.pos = Vec3(0., -5119., 184.)
+        ball.vel.y = -6600
+        arena.set_ball(ball)
+
+        with tempfile.TemporaryDirectory() as folder:
+            path = os.path.join(folder, "episode.rsrp")
+            recorder = ArenaRecorder(path, every_n_ticks=2)
+            arena.attach_recorder(recorder)
+            arena.step(10)
+            self.assertIs(arena.detach_recorder(), recorder)
+            recorder.close()
+
+            self.assertEqual(recorder.num_frames, 6)
+
+            reader = ReplayReader(path)
+            self.assertEqual(reader.every_n_ticks, 2)
+
+            frames = list(reader)
+            self.assertEqual(len(frames), 6)
+            self.assertEqual([frame.tick_count for frame in frames], list(range(0, 12, 2)))
+            self.assertEqual(frames[-1].game_state.cars[0].state.last_controls.throttle, 1)
+
+            goals = [event for frame in frames for event in frame.events if event.kind == ReplayEventKind.Goal]
+            self.assertEqual(len(goals), 1)
+            self.assertEqual(goals[0].team, Team.Orange)
+
+    def test_corrupt_replay(self):
+        arena = Arena(GameMode.Soccar, 120)
+        arena.add_car(Team.Blue, CarConfig.octane())
+        no_cars = Arena(GameMode.Soccar, 120).get_game_state().to_bytes()
+        game_state = arena.get_game_state().to_bytes()
+        car_num_bytes = len(game_state) - len(no_cars)
+
+        with tempfile.TemporaryDirectory() as folder:
+            path = os.path.join(folder, "episode.rsrp")
+            recorder = ArenaRecorder(path)
+            arena.attach_recorder(recorder)
+            arena.detach_recorder()
+            recorder.close()
+
+            with open(path, "rb") as file:
+                data = bytearray(file.read())
+
+            # the car's team comes after its id
+            team = data.index(game_state) + len(game_state) - car_num_bytes + 4
+            data[team] = 7
+            with open(path, "wb") as file:
+                file.write(data)
+
+            with self.assertRaises(ValueError):
+                list(ReplayReader(path))
+
+            # a frame claiming 4 GiB
+            with open(path, "wb") as file:
+                file.write(data[:10] + b"\xff\xff\xff\xff")
+
+            with self.assertRaises(ValueError):
+                list(ReplayReader(path))
+
+    def test_step_until(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+
+        ticks, event = arena.step_until(10)
+        self.assertEqual(ticks, 10)
+        self.assertIsNone(event)
+
+        ball = arena.get_ball()
+        ball.pos = Vec3(0., -5119., 184.)
+        ball.vel = ball.vel.with_y(-6600)
+        arena.set_ball(ball)
+
+        ticks, event = arena.step_until(120)
+        self.assertLess(ticks, 120)
+        self.assertEqual(event.kind, ReplayEventKind.Goal)
+        self.assertEqual(event.team, Team.Orange)
+        self.assertEqual(event.tick_count, arena.get_tick_count())
+
+        car = arena.get_car(car_id)
+        car.pos = Vec3(0., 0., 17.)
+        car.rot_mat = RotMat.from_angles(0, 1.5707963, 0)
+        arena.set_car(car_id, car)
+        arena.set_ball(Ball(pos=Vec3(0., 400., 93.)))
+        arena.set_car_controls(car_id, CarControls(throttle=1, boost=True))
+
+        ticks, event = arena.step_until(240, on_goal=False, on_touch=True)
+        self.assertLess(ticks, 240)
+        self.assertEqual(event.kind, ReplayEventKind.Touch)
+        self.assertEqual(event.car_id, car_id)
+
+        # there are no goals to wait for in the void
+        void = Arena(GameMode.TheVoid, 120)
+        with self.assertRaises(ValueError):
+            void.step_until(10)
+        self.assertEqual(void.step_until(10, on_goal=False, on_demo=True), (10, None))
+
+    def test_step_with_schedule(self):
+        arenas = [Arena(GameMode.Soccar, 120) for _ in range(2)]
+        car_id = [arena.add_car(Team.Blue, CarConfig.octane()) for arena in arenas][0]
+
+        arenas[0].step_with_schedule([(60, car_id, CarControls(throttle=1, jump=True)), (0, car_id, CarControls(throttle=1)), (75, car_id, CarControls())])
+        self.assertEqual(arenas[0].get_tick_count(), 76)
+        self.assertEqual(arenas[0].get_car(car_id).last_controls, CarControls())
+
+        arenas[1].set_car_controls(car_id, CarControls(throttle=1))
+        arenas[1].step(60)
+        arenas[1].set_car_controls(car_id, CarControls(throttle=1, jump=True))
+        arenas[1].step(15)
+        arenas[1].set_car_controls(car_id, CarControls())
+        arenas[1].step(1)
+        self.assertEqual(arenas[0].state_hash(), arenas[1].state_hash())
+
+        arenas[0].step_with_schedule([(0, car_id, CarControls(boost=True))], ticks_to_simulate=10)
+        self.assertEqual(arenas[0].get_tick_count(), 86)
+
+        with self.assertRaises(IndexError):
+            arenas[0].step_with_schedule([(0, car_id + 1, CarControls())])
+        with self.assertRaises(ValueError):
+            arenas[0].step_with_schedule([(10, car_id, CarControls())], ticks_to_simulate=10)
+        # more ticks than RocketSim can step at once
+        with self.assertRaises(ValueError):
+            arenas[0].step_with_schedule([(2**32 - 1, car_id, CarControls())])
+        with self.assertRaises(ValueError):
+            arenas[0].step_with_schedule([], ticks_to_simulate=2**31)
+        self.assertEqual(arenas[0].get_tick_count(), 86)
+
+    def test_replay_player(self):
+        arena = Arena(GameMode.Soccar, 120)
+        car_id = arena.add_car(Team.Blue, CarConfig.octane())
+        arena.set_car_controls(car_id, CarControls(throttle=1, steer=0.5))
+
+        with tempfile.TemporaryDirectory() as folder:
+            path = os.path.join(folder, "episode.rsrp")
+            recorder = ArenaRecorder(path)
+            arena.attach_recorder(recorder)
+            arena.step(30)
+            arena.set_car_controls(car_id, CarControls(boost=True, jump=True))
+            arena.step(30)
+
+            ball = arena.get_ball()
+            ball.pos.x += 500
+            arena.set_ball(ball)
+            arena.step(5)
+            recorder.close()
+
+            player = ReplayPlayer(path)
+            self.assertEqual(player.num_frames, 66)
+            self.assertIsNone(player.step())
+
+            divergence = player.run()
+            self.assertIsNotNone(divergence)
+            self.assertEqual(divergence.tick_count, 61)
+            self.assertEqual(divergence.field, "ball.pos.x")
+            self.assertEqual(player.frame_index, 61)
+
+            # every field is compared, not just the physics
+            arena.set_pad_state(0, BoostPadState(is_active=False, cooldown=4))
+            recorder = ArenaRecorder(path)
+            arena.attach_recorder(recorder)
+            arena.set_pad_state(0, BoostPadState(is_active=False, cooldown=2))
+            arena.step(1)
+            recorder.close()
+
+            divergence = ReplayPlayer(path).run()
+            self.assertEqual(divergence.field, "pads[0].state.cooldown")
+
     def test_goal_scored_callback(self):
         arena = Arena(GameMode.Soccar, 120)
         arena.add_car(Team.Orange, CarConfig.octane())
@@ -133,6 +1050,21 @@ class TestArena(unittest.TestCase):
 
         self.assertNotEqual(ball3, ball2)
 
+    def test_goal_scored_callback_error(self):
+        arena = Arena(GameMode.Soccar, 120)
+        ball = arena.get_ball()
+        ball.pos = Vec3(0., -5119., 184.)
+        ball.vel.y = -6600
+        arena.set_ball(ball)
+
+        # the arena keeps the callback alive, and its exception is raised once the step finishes
+        arena.set_goal_scored_callback(lambda team: 1 / 0)
+        with self.assertRaises(ZeroDivisionError):
+            arena.step(4)
+        self.assertEqual(arena.get_tick_count(), 4)
+
+        arena.step(2)
+
     def test_game_state(self):
         arena = Arena(GameMode.Soccar, 120)
         arena.add_car(Team.Orange, CarConfig.octane())
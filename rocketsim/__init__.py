@@ -1,12 +1,20 @@
 import sys
 
 from .rocketsim import *
-from .rocketsim import sim
+from .rocketsim import field, rewards, rlbot, sim, state_setters
 
 __doc__ = rocketsim.__doc__
 if hasattr(rocketsim, "__all__"):
     __all__ = rocketsim.__all__
 
 sys.modules["rocketsim.sim"] = sim
+sys.modules["rocketsim.field"] = field
+sys.modules["rocketsim.rlbot"] = rlbot
+sys.modules["rocketsim.state_setters"] = state_setters
+sys.modules["rocketsim.rewards"] = rewards
 
-rocketsim.init()
+try:
+    rocketsim.init()
+except FileNotFoundError:
+    # meshes aren't in the default folder, rocketsim.init() must be called with the right path before creating an Arena
+    pass
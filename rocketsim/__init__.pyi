@@ -1,5 +1,22 @@
+from typing import Optional
+
 from rocketsim.sim import Ball, BoostPadState, Car, CarConfig, Team
 
+def init(collision_meshes_folder: Optional[str] = None) -> InitInfo: ...
+def init_from_temp_folder(collision_meshes: dict[str, bytes]) -> InitInfo: ...
+def is_initialized() -> bool: ...
+def serve(port: int, host: str = "127.0.0.1", max_connections: Optional[int] = None) -> None: ...
+
+class InitInfo:
+    folder: str
+    meshes_loaded: dict[str, int]
+    missing: list[str]
+    unknown_files: list[str]
+    is_complete: bool
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
 class Vec3:
     x: float
     y: float
@@ -8,10 +25,12 @@ class Vec3:
     def __init__(x: float = 0, y: float = 0, z: float = 0) -> Vec3: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
     def with_x(self, x: float) -> Vec3: ...
     def with_y(self, y: float) -> Vec3: ...
     def with_z(self, z: float) -> Vec3: ...
+    def approx_eq(self, other: Vec3, tolerance: float = 1e-4) -> bool: ...
 
 class RotMat:
     forward: Vec3
@@ -21,11 +40,23 @@ class RotMat:
     def __init__(forward: Vec3, right: Vec3, up: Vec3) -> RotMat: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
     @staticmethod
     def identity() -> RotMat: ...
     @staticmethod
     def from_angles(pitch: float, yaw: float, roll: float) -> RotMat: ...
+    def approx_eq(self, other: RotMat, tolerance: float = 1e-4) -> bool: ...
+
+class FieldDiff:
+    field: str
+    old: float
+    new: float
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
 
 class GameState:
     tick_rate: float
@@ -37,6 +68,11 @@ class GameState:
     def __init__(tick_count: float=0, tick_rate: float=0, ball: Ball=Ball(), ball_rot: RotMat=RotMat(), cars: list[CarInfo]=[]) -> GameState: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def to_bytes(self) -> bytes: ...
+    @staticmethod
+    def from_bytes(bytes: bytes) -> GameState: ...
+    def diff(self, other: GameState, tolerance: float = 1e-4) -> list[FieldDiff]: ...
 
 class CarInfo:
     id: int
@@ -47,6 +83,7 @@ class CarInfo:
     def __init__(id: int, team: Team=Team.Blue, state: Car=Car(), config: CarConfig=CarConfig()) -> CarInfo: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
 class BoostPad:
     is_big: bool
@@ -56,3 +93,4 @@ class BoostPad:
     def __init__(is_big: bool=False, position: Vec3=Vec3(), state: BoostPadState=BoostPadState()) -> BoostPad: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
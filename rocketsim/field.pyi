@@ -0,0 +1,40 @@
+from typing import Optional
+
+from rocketsim import Vec3
+from rocketsim.sim import BoostPadStatic, GameMode, Team
+
+class Goal:
+    team: Team
+    center: Vec3
+    width: float
+    height: float
+    depth: float
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class Field:
+    gamemode: GameMode
+    extent_x: float
+    extent_y: float
+    height: float
+    corner_offset: float
+    goal_score_threshold_y: float
+    goals: list[Goal]
+    boost_pads: list[BoostPadStatic]
+
+    @staticmethod
+    def soccar() -> Field: ...
+    @staticmethod
+    def the_void() -> Field: ...
+    @staticmethod
+    def from_gamemode(gamemode: GameMode) -> Field: ...
+    def __str__(self) -> str: ...
+
+def get_field(gamemode: GameMode = GameMode.Soccar) -> Field: ...
+def own_goal(team: Team, gamemode: GameMode = GameMode.Soccar) -> Optional[Goal]: ...
+def opponent_goal(team: Team, gamemode: GameMode = GameMode.Soccar) -> Optional[Goal]: ...
+def is_in_goal(pos: Vec3, team: Team, gamemode: GameMode = GameMode.Soccar) -> bool: ...
+def distance_to_wall(pos: Vec3, gamemode: GameMode = GameMode.Soccar) -> float: ...
@@ -0,0 +1,27 @@
+from enum import Enum
+from typing import Tuple
+
+import numpy
+
+from rocketsim.sim import Arena
+
+class RewardTerm(Enum):
+    VelocityToBall = 0
+    BallVelocityToGoal = 1
+    Touch = 2
+    Goal = 3
+    BoostPickup = 4
+    BoostUsage = 5
+    FacingBall = 6
+    Demo = 7
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
+class RewardFunction:
+    terms: list[Tuple[RewardTerm, float]]
+    last_terms: numpy.ndarray
+
+    def __init__(self, terms: list[Tuple[RewardTerm, float]]) -> RewardFunction: ...
+    def reset(self, arena: Arena): ...
+    def compute(self, arena: Arena) -> numpy.ndarray: ...
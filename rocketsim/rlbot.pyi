@@ -0,0 +1,8 @@
+from typing import Any
+
+from rocketsim import GameState
+from rocketsim.sim import GameMode
+
+def game_state_to_packet(game_state: GameState) -> dict[str, Any]: ...
+def packet_to_game_state(packet: Any, game_state: GameState) -> GameState: ...
+def get_field_info(gamemode: GameMode = GameMode.Soccar) -> dict[str, Any]: ...
@@ -1,6 +1,9 @@
+import asyncio
 from enum import Enum
 from typing import Callable, Optional, Tuple
 
+import numpy
+
 from rocketsim import GameState, RotMat, Vec3
 
 class Team(Enum):
@@ -10,6 +13,23 @@ class Team(Enum):
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
 
+class CarPreset(Enum):
+    Octane = 0
+    Dominus = 1
+    Plank = 2
+    Breakout = 3
+    Hybrid = 4
+    Merc = 5
+
+    @property
+    def name(self) -> str: ...
+    @staticmethod
+    def from_name(name: str) -> CarPreset: ...
+    @staticmethod
+    def all() -> list[CarPreset]: ...
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
 class GameMode(Enum):
     Soccar = 0
     TheVoid = 1
@@ -30,6 +50,7 @@ class BallHitInfo:
     ) -> BallHitInfo: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
 class Ball:
     pos: Vec3
@@ -38,6 +59,7 @@ class Ball:
 
     def __init__(pos: Vec3 = Vec3(), vel: Vec3 = Vec3(), ang_vel: Vec3 = Vec3()) -> Ball: ...
     def __str__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
 class CarControls:
     throttle: float
@@ -52,6 +74,7 @@ class CarControls:
     def __init__(throttle: float = 0, steer: float = 0, pitch: float = 0, yaw: float = 0, roll: float = 0, jump: bool = False, boost: bool = False, handbrake: bool = False) -> CarControls: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
 class Car:
     pos: Vec3
@@ -117,8 +140,11 @@ class Car:
     ) -> Car: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
     def get_contacting_car(self, arena: Arena) -> Optional[Car]: ...
+    def hitbox_corners(self, config: CarConfig) -> list[Vec3]: ...
+    def approx_eq(self, other: Car, tolerance: float = 1e-4) -> bool: ...
 
 class WheelPairConfig:
     wheel_radius: float
@@ -128,6 +154,7 @@ class WheelPairConfig:
     def __init__(wheel_radius: float = 0, suspension_rest_length: float = 0, connection_point_offset: Vec3 = Vec3()) -> WheelPairConfig: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
 class CarConfig:
     hitbox_size: Vec3
@@ -137,14 +164,15 @@ class CarConfig:
     dodge_deadzone: float
 
     def __init__(
-        hitbox_size: Vec3 = Vec3(),
-        hitbox_pos_offset: Vec3 = Vec3(),
-        front_wheel: WheelPairConfig = WheelPairConfig(),
-        back_wheel: WheelPairConfig = WheelPairConfig(),
-        dodge_deadzone: float = 0.5
+        hitbox_size: Optional[Vec3] = None,
+        hitbox_pos_offset: Optional[Vec3] = None,
+        front_wheels: Optional[WheelPairConfig] = None,
+        back_wheels: Optional[WheelPairConfig] = None,
+        dodge_deadzone: Optional[float] = None
     ) -> CarConfig: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
     @staticmethod
     def octane() -> CarConfig: ...
@@ -158,12 +186,29 @@ class CarConfig:
     def plank() -> CarConfig: ...
     @staticmethod
     def merc() -> CarConfig: ...
+    @staticmethod
+    def from_preset(preset: CarPreset) -> CarConfig: ...
+    @staticmethod
+    def from_name(name: str) -> CarConfig: ...
+    @staticmethod
+    def all_presets() -> list[tuple[CarPreset, CarConfig]]: ...
+    @property
+    def preset(self) -> Optional[CarPreset]: ...
+    def validate(self) -> None: ...
+    @property
+    def hitbox_corners(self) -> list[Vec3]: ...
+    @property
+    def wheelbase(self) -> float: ...
+    @property
+    def track_width(self) -> float: ...
 
 class BoostPadStatic:
     pos: Vec3
     is_big: bool
 
     def __str__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
 
 class BoostPadState:
     is_active: bool
@@ -174,22 +219,200 @@ class BoostPadState:
     def __init__(is_active: bool=True, cooldown: float=0, cur_locked_car_id: int=0, prev_locked_car_id: int=0) -> BoostPadState: ...
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class CarObb:
+    center: Vec3
+    half_extents: Vec3
+    rot_mat: RotMat
+    corners: list[Vec3]
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
+class RayHit:
+    pos: Vec3
+    normal: Vec3
+    distance: float
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class CarView:
+    @property
+    def pos(self) -> Vec3: ...
+    @property
+    def rot_mat(self) -> RotMat: ...
+    @property
+    def vel(self) -> Vec3: ...
+    @property
+    def ang_vel(self) -> Vec3: ...
+    @property
+    def is_on_ground(self) -> bool: ...
+    @property
+    def has_jumped(self) -> bool: ...
+    @property
+    def has_double_jumped(self) -> bool: ...
+    @property
+    def has_flipped(self) -> bool: ...
+    @property
+    def last_rel_dodge_torque(self) -> Vec3: ...
+    @property
+    def jump_time(self) -> float: ...
+    @property
+    def flip_time(self) -> float: ...
+    @property
+    def is_jumping(self) -> bool: ...
+    @property
+    def air_time_since_jump(self) -> float: ...
+    @property
+    def boost(self) -> float: ...
+    @property
+    def time_spent_boosting(self) -> float: ...
+    @property
+    def is_supersonic(self) -> bool: ...
+    @property
+    def supersonic_time(self) -> float: ...
+    @property
+    def handbrake_val(self) -> float: ...
+    @property
+    def is_auto_flipping(self) -> bool: ...
+    @property
+    def auto_flip_timer(self) -> float: ...
+    @property
+    def auto_flip_torque_scale(self) -> float: ...
+    @property
+    def has_contact(self) -> bool: ...
+    @property
+    def contact_normal(self) -> Vec3: ...
+    @property
+    def other_car_id(self) -> int: ...
+    @property
+    def cooldown_timer(self) -> float: ...
+    @property
+    def is_demoed(self) -> bool: ...
+    @property
+    def demo_respawn_timer(self) -> float: ...
+    @property
+    def ball_hit_info(self) -> BallHitInfo: ...
+    @property
+    def last_controls(self) -> CarControls: ...
+    @property
+    def id(self) -> int: ...
+    @property
+    def is_valid(self) -> bool: ...
+
+    def get(self) -> Car: ...
+    def __repr__(self) -> str: ...
+
+class BallView:
+    @property
+    def pos(self) -> Vec3: ...
+    @property
+    def vel(self) -> Vec3: ...
+    @property
+    def ang_vel(self) -> Vec3: ...
+    @property
+    def rot_mat(self) -> RotMat: ...
+    @property
+    def is_valid(self) -> bool: ...
+
+    def get(self) -> Ball: ...
+    def __repr__(self) -> str: ...
+
+class ReplayEventKind(Enum):
+    Goal = 0
+    Touch = 1
+    Demo = 2
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
+class ReplayEvent:
+    kind: ReplayEventKind
+    tick_count: int
+    team: Team
+    car_id: Optional[int]
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
+class ReplayFrame:
+    tick_count: int
+    game_state: GameState
+    events: list[ReplayEvent]
+
+    def __str__(self) -> str: ...
+
+class ArenaRecorder:
+    every_n_ticks: int
+    num_frames: int
+
+    def __init__(self, path: str, every_n_ticks: int = 1) -> ArenaRecorder: ...
+    def flush(self): ...
+    def close(self): ...
+
+class ReplayReader:
+    version: int
+    every_n_ticks: int
+
+    def __init__(self, path: str) -> ReplayReader: ...
+    def render_frames(self, folder: str, width: int = 600, height: int = 800, gamemode: GameMode = GameMode.Soccar) -> list[str]: ...
+    def __iter__(self) -> ReplayReader: ...
+    def __next__(self) -> ReplayFrame: ...
+
+class ReplayDivergence:
+    tick_count: int
+    field: str
+    recorded: float
+    simulated: float
+
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
+class ReplayPlayer:
+    tolerance: float
+    arena: Arena
+    num_frames: int
+    frame_index: int
+    is_finished: bool
+
+    def __init__(self, path: str, gamemode: GameMode = GameMode.Soccar, tolerance: float = 0.01) -> ReplayPlayer: ...
+    def step(self) -> Optional[ReplayDivergence]: ...
+    def run(self) -> Optional[ReplayDivergence]: ...
 
 class Arena:
     def __init__(gamemode: GameMode=GameMode.Soccar, tick_rate: float=120) -> Arena: ...
     def get_tick_rate(self) -> float: ...
     def get_tick_count(self) -> int: ...
     def step(self, ticks_to_simulate: int = 1): ...
+    def step_with_schedule(self, schedule: list[Tuple[int, int, CarControls]], ticks_to_simulate: Optional[int] = None): ...
+    def step_record(self, ticks_to_simulate: int, fields: list[str] = ["pos", "vel", "rot_mat"], every_n_ticks: int = 1, car_ids: Optional[list[int]] = None) -> numpy.ndarray: ...
+    def step_until(self, max_ticks: int, on_goal: bool = True, on_touch: bool = False, on_demo: bool = False) -> Tuple[int, Optional[ReplayEvent]]: ...
+    def step_async(self, ticks_to_simulate: int = 1, fields: list[str] = ["pos", "vel", "rot_mat"], car_ids: Optional[list[int]] = None) -> asyncio.Future[numpy.ndarray]: ...
+    def attach_recorder(self, recorder: ArenaRecorder): ...
+    def detach_recorder(self) -> Optional[ArenaRecorder]: ...
 
     def get_ball(self) -> Ball: ...
     def set_ball(self, ball: Ball): ...
     def get_ball_rotation(self) -> RotMat: ...
+    def ball_view(self) -> BallView: ...
 
     def num_cars(self) -> int: ...
     def add_car(self, team: Team, config: CarConfig) -> int: ...
     def get_cars(self, id: int) -> list[int]: ...
     def get_car(self, id: int) -> Car: ...
+    def remove_car(self, id: int): ...
+    def car_view(self, id: int) -> CarView: ...
+    def get_car_obb(self, id: int) -> CarObb: ...
+    def get_car_obbs(self) -> numpy.ndarray: ...
     def set_car(self, id: int, car: Car): ...
+    def place_car_on_ground(self, id: int, pos: Vec3, yaw: float = 0): ...
+    def place_car_on_wall(self, id: int, pos: Vec3, normal: Vec3): ...
+    def place_car_airborne(self, id: int, pos: Vec3, rot_mat: RotMat, vel: Vec3 = Vec3(), ang_vel: Vec3 = Vec3(), has_flip: bool = True): ...
+    def set_car_boost(self, id: int, amount: float): ...
     def set_car_controls(self, id: int, controls: CarControls): ...
     def set_all_controls(self, controls: list[Tuple[int, CarControls]]): ...
 
@@ -199,5 +422,9 @@ class Arena:
     def set_pad_state(self, index: int, boost_pad: BoostPadState): ...
 
     def get_game_state(self) -> GameState: ...
+    def state_hash(self) -> int: ...
     def set_game_state(self, game_state: GameState): ...
+    def render_topdown(self, width: int = 600, height: int = 800) -> str: ...
+    def raycast(self, origin: Vec3, direction: Vec3, max_dist: float) -> Optional[RayHit]: ...
+    def raycast_batch(self, origins: numpy.ndarray, directions: numpy.ndarray, max_dist: float) -> numpy.ndarray: ...
     def set_goal_scored_callback(self, callback: Callable[[Team], None]): ...
@@ -0,0 +1 @@
+# Dummy file so IDEs don't complain about not being able to resolve from source
@@ -0,0 +1,11 @@
+from typing import Optional
+
+from rocketsim import GameState
+from rocketsim.sim import Arena, Team
+
+def random_ball(arena: Arena, seed: Optional[int] = None, max_speed: float = 2000) -> GameState: ...
+def random_cars(arena: Arena, seed: Optional[int] = None, airborne_chance: float = 0.2) -> GameState: ...
+def random_boost(arena: Arena, seed: Optional[int] = None, min_boost: float = 0, max_boost: float = 100) -> GameState: ...
+def random_state(arena: Arena, seed: Optional[int] = None) -> GameState: ...
+def kickoff(arena: Arena, seed: Optional[int] = None) -> GameState: ...
+def goalie(arena: Arena, team: Team = Team.Blue, seed: Optional[int] = None) -> GameState: ...
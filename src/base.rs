@@ -1,9 +1,16 @@
-use pyo3::{prelude::*, PyClass};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use pyo3::{prelude::*, pyclass::CompareOp, PyClass};
 use rocketsim_rs::{
     glam_ext::glam::{Mat3, Mat3A, Quat, Vec3 as GVec3},
     math::{Angle, RotMat as CRotMat, Vec3 as CVec3},
 };
 
+use crate::diff::{floats_differ, Differ, DEFAULT_TOLERANCE};
+
 #[inline]
 pub fn repr_bool(b: bool) -> &'static str {
     if b {
@@ -13,22 +20,6 @@ pub fn repr_bool(b: bool) -> &'static str {
     }
 }
 
-pub trait PyDefault
-where
-    Self: Sized,
-{
-    fn py_default(py: Python) -> PyResult<Self>;
-}
-
-impl<T> PyDefault for T
-where
-    T: Default,
-{
-    fn py_default(_py: Python) -> PyResult<Self> {
-        Ok(Self::default())
-    }
-}
-
 pub trait FromGil<T>: Sized {
     fn from_gil(py: Python, obj: T) -> PyResult<Self>;
 }
@@ -37,31 +28,6 @@ pub trait RemoveGil<T> {
     fn remove_gil(self, py: Python) -> T;
 }
 
-impl<T, U> RemoveGil<T> for Py<U>
-where
-    U: PyClass + Copy,
-    T: From<U>,
-{
-    #[inline]
-    fn remove_gil(self, py: Python) -> T {
-        T::from(*self.borrow(py))
-    }
-}
-
-#[macro_export]
-macro_rules! new_gil {
-    ($t:ty, $py:expr, $e:expr) => {
-        Py::new($py, <$t>::from_gil($py, $e)?)?
-    };
-}
-
-#[macro_export]
-macro_rules! new_gil_default {
-    ($t:ty, $py:expr) => {
-        Py::new($py, <$t>::from_gil($py, <$t>::py_default($py)?)?)?
-    };
-}
-
 pub trait IntoGil<T>: Sized {
     fn into_gil(self, py: Python) -> PyResult<T>;
 }
@@ -86,105 +52,225 @@ where
     }
 }
 
-#[pyfunction]
-#[inline]
-pub fn init(collision_meshes_folder: Option<&str>) {
-    rocketsim_rs::init(collision_meshes_folder);
+/// Structural equality for classes that hold other Python objects
+pub trait PyEq {
+    fn py_eq(&self, py: Python, other: &Self) -> bool;
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim")]
-#[derive(Clone, Debug)]
-pub struct RotMat {
-    pub forward: Py<Vec3>,
-    pub right: Py<Vec3>,
-    pub up: Py<Vec3>,
+impl<T> PyEq for Py<T>
+where
+    T: PyClass + PyEq,
+{
+    #[inline]
+    fn py_eq(&self, py: Python, other: &Self) -> bool {
+        // like Python's containers, the same object is always equal to itself
+        self.is(other) || self.borrow(py).py_eq(py, &other.borrow(py))
+    }
 }
 
-impl PyDefault for RotMat {
+impl<T> PyEq for Vec<T>
+where
+    T: PyEq,
+{
     #[inline]
-    fn py_default(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            forward: new_gil_default!(Vec3, py),
-            right: new_gil_default!(Vec3, py),
-            up: new_gil_default!(Vec3, py),
-        })
+    fn py_eq(&self, py: Python, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.py_eq(py, b))
     }
 }
 
-impl FromGil<CRotMat> for RotMat {
+/// The result of `__richcmp__` for classes that can only be compared with `==` and `!=`
+#[inline]
+pub fn richcmp_eq(py: Python, equal: bool, op: CompareOp) -> PyObject {
+    match op {
+        CompareOp::Eq => equal.into_py(py),
+        CompareOp::Ne => (!equal).into_py(py),
+        _ => py.NotImplemented(),
+    }
+}
+
+/// Hashes a float so that `0.0` and `-0.0`, which compare equal, also hash the same
+#[inline]
+pub fn hash_f32(x: f32, state: &mut impl Hasher) {
+    (x + 0.).to_bits().hash(state);
+}
+
+/// Finishes a `__hash__` from the fields hashed by `f`
+#[inline]
+pub fn finish_hash(f: impl FnOnce(&mut DefaultHasher)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    f(&mut hasher);
+    hasher.finish()
+}
+
+/// Declares `$name`, the subclass of `$base` that nested state values like `car.pos` are returned as
+///
+/// The values are stored inline, so setting an attribute on `$name` also sets the field it was read from
+/// on its owner, which keeps `car.pos.z = 17` changing `car`.
+/// Only that attribute is changed on the owner, and the handle is then refreshed from it.
+/// Every read makes a new handle.
+/// A value read from a read-only field raises an `AttributeError` when it's changed.
+macro_rules! nested_value {
+    ($name:ident, $base:ty) => {
+        #[pyclass(extends = $base)]
+        pub struct $name {
+            owner: PyObject,
+            field: &'static str,
+        }
+
+        impl $name {
+            /// `value` as read from `owner.field`
+            pub fn wrap(py: Python, owner: PyObject, field: &'static str, value: $base) -> PyResult<PyObject> {
+                Ok(Py::new(py, pyo3::PyClassInitializer::from(value).add_subclass(Self { owner, field }))?.into_py(py))
+            }
+        }
+
+        // pyo3 defines the `__setattr__` slot's impls inside a function, which this lint doesn't expect from a local macro
+        #[allow(non_local_definitions)]
+        const _: () = {
+            #[pymethods]
+            impl $name {
+                /// The owner's current value of the field this was read from, read again through every handle above it
+                fn _current(&self, py: Python) -> PyResult<PyObject> {
+                    let owner = if self.owner.as_ref(py).hasattr("_current")? {
+                        self.owner.call_method0(py, "_current")?
+                    } else {
+                        self.owner.clone_ref(py)
+                    };
+                    owner.getattr(py, self.field)
+                }
+
+                fn __setattr__(slf: &PyCell<Self>, name: &str, value: &PyAny) -> PyResult<()> {
+                    let py = slf.py();
+                    let setter = py.get_type::<$base>().getattr(name)?;
+                    let (owner, field, current) = {
+                        let this = slf.borrow();
+                        (this.owner.clone_ref(py), this.field, this._current(py)?)
+                    };
+
+                    // only `name` is changed on the owner's current value,
+                    // so a handle read before another change doesn't undo it
+                    // the base class's own setter checks the type
+                    setter.call_method1("__set__", (current.as_ref(py), value))?;
+                    owner.setattr(py, field, current.clone_ref(py))?;
+
+                    *slf.borrow_mut().as_mut() = current.extract::<$base>(py)?;
+                    Ok(())
+                }
+            }
+        };
+    };
+}
+
+pub(crate) use nested_value;
+
+#[pyclass(set_all, subclass, module = "rocketsim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RotMat {
+    pub forward: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+}
+
+nested_value!(RotMatRef, RotMat);
+
+impl From<CRotMat> for RotMat {
     #[inline]
-    fn from_gil(py: Python, mat: CRotMat) -> PyResult<Self> {
-        Ok(Self {
-            forward: new_gil!(Vec3, py, mat.forward),
-            right: new_gil!(Vec3, py, mat.right),
-            up: new_gil!(Vec3, py, mat.up),
-        })
+    fn from(mat: CRotMat) -> Self {
+        Self {
+            forward: mat.forward.into(),
+            right: mat.right.into(),
+            up: mat.up.into(),
+        }
     }
 }
 
-impl RemoveGil<CRotMat> for RotMat {
+impl From<RotMat> for CRotMat {
     #[inline]
-    fn remove_gil(self, py: Python) -> CRotMat {
-        CRotMat {
-            forward: self.forward.remove_gil(py),
-            right: self.right.remove_gil(py),
-            up: self.up.remove_gil(py),
+    fn from(mat: RotMat) -> Self {
+        Self {
+            forward: mat.forward.into(),
+            right: mat.right.into(),
+            up: mat.up.into(),
         }
     }
 }
 
-impl FromGil<Mat3> for RotMat {
+impl From<Mat3> for RotMat {
     #[inline]
-    fn from_gil(py: Python, mat: Mat3) -> PyResult<Self> {
-        Ok(Self {
-            forward: new_gil!(Vec3, py, mat.x_axis),
-            right: new_gil!(Vec3, py, mat.y_axis),
-            up: new_gil!(Vec3, py, mat.z_axis),
-        })
+    fn from(mat: Mat3) -> Self {
+        Self {
+            forward: mat.x_axis.into(),
+            right: mat.y_axis.into(),
+            up: mat.z_axis.into(),
+        }
     }
 }
 
-impl RemoveGil<Mat3> for RotMat {
+impl From<RotMat> for Mat3 {
     #[inline]
-    fn remove_gil(self, py: Python) -> Mat3 {
-        Mat3::from_cols(self.forward.remove_gil(py), self.right.remove_gil(py), self.up.remove_gil(py))
+    fn from(mat: RotMat) -> Self {
+        Self::from_cols(mat.forward.into(), mat.right.into(), mat.up.into())
     }
 }
 
-impl FromGil<Quat> for RotMat {
+impl From<Quat> for RotMat {
     #[inline]
-    fn from_gil(py: Python, quat: Quat) -> PyResult<Self> {
-        Self::from_gil(py, Mat3::from_quat(quat))
+    fn from(quat: Quat) -> Self {
+        Self::from(Mat3::from_quat(quat))
     }
 }
 
-impl RemoveGil<Quat> for RotMat {
+impl From<RotMat> for Quat {
     #[inline]
-    fn remove_gil(self, py: Python) -> Quat {
-        Quat::from_mat3(&self.remove_gil(py))
+    fn from(mat: RotMat) -> Self {
+        Self::from_mat3(&mat.into())
     }
 }
 
+impl RotMat {
+    pub const ZERO: Self = Self {
+        forward: Vec3::ZERO,
+        right: Vec3::ZERO,
+        up: Vec3::ZERO,
+    };
+    pub const IDENTITY: Self = Self {
+        forward: Vec3::X,
+        right: Vec3::Y,
+        up: Vec3::Z,
+    };
+}
+
 #[pymethods]
 impl RotMat {
     #[new]
     #[inline]
-    fn __new__(py: Python, forward: Option<Py<Vec3>>, right: Option<Py<Vec3>>, up: Option<Py<Vec3>>) -> PyResult<Self> {
-        Ok(Self {
-            forward: forward.unwrap_or(new_gil_default!(Vec3, py)),
-            right: right.unwrap_or(new_gil_default!(Vec3, py)),
-            up: up.unwrap_or(new_gil_default!(Vec3, py)),
-        })
+    #[pyo3(signature = (forward=Vec3::ZERO, right=Vec3::ZERO, up=Vec3::ZERO))]
+    fn __new__(forward: Vec3, right: Vec3, up: Vec3) -> Self {
+        Self { forward, right, up }
     }
 
     #[inline]
     #[staticmethod]
-    pub fn identity(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            forward: new_gil!(Vec3, py, Vec3::X),
-            right: new_gil!(Vec3, py, Vec3::Y),
-            up: new_gil!(Vec3, py, Vec3::Z),
-        })
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+
+    #[getter]
+    fn forward(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.forward;
+        Vec3Ref::wrap(py, slf.into_py(py), "forward", value)
+    }
+
+    #[getter]
+    fn right(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.right;
+        Vec3Ref::wrap(py, slf.into_py(py), "right", value)
+    }
+
+    #[getter]
+    fn up(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.up;
+        Vec3Ref::wrap(py, slf.into_py(py), "up", value)
     }
 
     #[inline]
@@ -193,23 +279,31 @@ impl RotMat {
     }
 
     #[inline]
-    pub fn __repr__(&self, py: Python) -> String {
-        format!(
-            "RotMat({}, {}, {})",
-            self.forward.borrow(py).__repr__(),
-            self.right.borrow(py).__repr__(),
-            self.up.borrow(py).__repr__()
-        )
+    pub fn __repr__(&self) -> String {
+        format!("RotMat({}, {}, {})", self.forward.__repr__(), self.right.__repr__(), self.up.__repr__())
     }
 
     #[inline]
     #[staticmethod]
-    fn from_angles(py: Python, pitch: f32, yaw: f32, roll: f32) -> PyResult<Self> {
-        CRotMat::from(Mat3A::from_quat(Quat::from(Angle { pitch, yaw, roll }))).into_gil(py)
+    pub fn from_angles(pitch: f32, yaw: f32, roll: f32) -> Self {
+        CRotMat::from(Mat3A::from_quat(Quat::from(Angle { pitch, yaw, roll }))).into()
+    }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    /// True if every component of every axis is within the tolerance of the other matrix's
+    #[pyo3(signature = (other, tolerance=DEFAULT_TOLERANCE))]
+    fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        let mut differ = Differ::new(tolerance);
+        differ.rot_mat("rot_mat", "", (*self).into(), (*other).into());
+        differ.diffs.is_empty()
     }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim")]
+#[pyclass(get_all, set_all, subclass, module = "rocketsim")]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vec3 {
     pub x: f32,
@@ -217,6 +311,8 @@ pub struct Vec3 {
     pub z: f32,
 }
 
+nested_value!(Vec3Ref, Vec3);
+
 impl From<GVec3> for Vec3 {
     #[inline]
     fn from(vec3: GVec3) -> Self {
@@ -259,6 +355,14 @@ impl Vec3 {
     pub const fn new(x: f32, y: f32, z: f32) -> Self {
         Self { x, y, z }
     }
+
+    /// For the `__hash__` of frozen classes that hold a `Vec3`
+    #[inline]
+    pub fn hash_components(&self, state: &mut impl Hasher) {
+        hash_f32(self.x, state);
+        hash_f32(self.y, state);
+        hash_f32(self.z, state);
+    }
 }
 
 #[pymethods]
@@ -281,7 +385,7 @@ impl Vec3 {
     }
 
     #[inline]
-    fn with_z(&self, z: f32) -> Self {
+    pub fn with_z(&self, z: f32) -> Self {
         Self::new(self.x, self.y, z)
     }
 
@@ -294,4 +398,16 @@ impl Vec3 {
     pub fn __repr__(&self) -> String {
         format!("Vec3(x={}, y={}, z={})", self.x, self.y, self.z)
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    /// True if every component is within the tolerance of the other vector's
+    #[inline]
+    #[pyo3(signature = (other, tolerance=DEFAULT_TOLERANCE))]
+    pub fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        !(floats_differ(self.x, other.x, tolerance) || floats_differ(self.y, other.y, tolerance) || floats_differ(self.z, other.z, tolerance))
+    }
 }
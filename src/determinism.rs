@@ -0,0 +1,219 @@
+use rocketsim_rs::{bytes::ToBytes, GameState as CGameState};
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// FNV-1a hash of the game state in the rocketsim_rs `bin` format, which doesn't depend on the platform or Rust version
+///
+/// The tick count and ball rotation are left out because `set_game_state` can't restore them,
+/// so an arena seeded from another arena's `GameState` hashes the same as it.
+/// Leaving out the ball rotation doesn't hide any divergence: the ball is a sphere, so its rotation never affects the physics,
+/// and its angular velocity is still hashed
+pub fn state_hash(mut game_state: CGameState) -> u64 {
+    game_state.tick_count = 0;
+    game_state.ball_rot = [0.; 4];
+
+    game_state
+        .to_bytes()
+        .into_iter()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Once};
+
+    use rocketsim_rs::{
+        autocxx::prelude::*,
+        cxx::UniquePtr,
+        math::Vec3,
+        sim::{Arena, CarConfig, CarControls, GameMode, Team},
+    };
+
+    use super::state_hash;
+
+    static INIT: Once = Once::new();
+
+    /// Loads a flat floor as the only collision mesh, so the tests don't need the real arena meshes
+    fn init() {
+        INIT.call_once(|| {
+            let folder = std::env::temp_dir().join(format!("rocketsim_test_meshes_{}", std::process::id()));
+            let soccar_folder = folder.join("soccar");
+            fs::create_dir_all(&soccar_folder).unwrap();
+
+            let tris: [[i32; 3]; 2] = [[0, 1, 2], [0, 2, 3]];
+            let vertices: [[f32; 3]; 4] = [[-82., -103., 0.], [82., -103., 0.], [82., 103., 0.], [-82., 103., 0.]];
+
+            let mut bytes = Vec::new();
+            bytes.extend((tris.len() as i32).to_le_bytes());
+            bytes.extend((vertices.len() as i32).to_le_bytes());
+            bytes.extend(tris.iter().flatten().flat_map(|index| index.to_le_bytes()));
+            bytes.extend(vertices.iter().flatten().flat_map(|val| val.to_le_bytes()));
+            fs::write(soccar_folder.join("floor.cmf"), bytes).unwrap();
+
+            rocketsim_rs::init(Some(&folder.to_string_lossy()));
+
+            // the meshes are kept in memory after init
+            fs::remove_dir_all(&folder).unwrap();
+        });
+    }
+
+    /// An arena with the ball in the air and two cars driving at it
+    fn new_arena() -> UniquePtr<Arena> {
+        init();
+
+        let mut arena = Arena::new(GameMode::SOCCAR, 120.).within_unique_ptr();
+
+        let mut ball = arena.pin_mut().get_ball();
+        ball.pos = Vec3::new(0., 0., 300.);
+        ball.vel = Vec3::new(200., -100., 500.);
+        arena.pin_mut().set_ball(ball);
+
+        let blue = arena.pin_mut().add_car(Team::BLUE, CarConfig::octane());
+        let orange = arena.pin_mut().add_car(Team::ORANGE, CarConfig::dominus());
+
+        let mut car = arena.pin_mut().get_car(orange);
+        car.pos = Vec3::new(0., 1500., 17.);
+        arena.pin_mut().set_car(orange, car).unwrap();
+
+        arena
+            .pin_mut()
+            .set_all_controls(&[
+                (
+                    blue,
+                    CarControls {
+                        throttle: 1.,
+                        steer: 0.3,
+                        boost: true,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    orange,
+                    CarControls {
+                        throttle: 1.,
+                        jump: true,
+                        ..Default::default()
+                    },
+                ),
+            ])
+            .unwrap();
+
+        // step once the controls are set, so `last_controls` matches them and `reseed` copies them
+        arena.pin_mut().step(30);
+
+        arena
+    }
+
+    /// A fresh arena with the same cars as `arena`, seeded with its game state
+    fn reseed(arena: &mut UniquePtr<Arena>) -> UniquePtr<Arena> {
+        let game_state = arena.pin_mut().get_game_state();
+
+        let mut seeded = Arena::new(GameMode::SOCCAR, game_state.tick_rate).within_unique_ptr();
+        for car in &game_state.cars {
+            let id = seeded.pin_mut().add_car(car.team, &car.config);
+            assert_eq!(id, car.id);
+            seeded.pin_mut().set_car_controls(id, car.state.last_controls).unwrap();
+        }
+
+        seeded.pin_mut().set_game_state(&game_state).unwrap();
+        seeded
+    }
+
+    fn hash(arena: &mut UniquePtr<Arena>) -> u64 {
+        state_hash(arena.pin_mut().get_game_state())
+    }
+
+    fn assert_lockstep(a: &mut UniquePtr<Arena>, b: &mut UniquePtr<Arena>, ticks: usize) {
+        assert_eq!(hash(a), hash(b));
+
+        for tick in 0..ticks {
+            a.pin_mut().step(1);
+            b.pin_mut().step(1);
+            assert_eq!(hash(a), hash(b), "arenas diverged after {} ticks", tick + 1);
+        }
+    }
+
+    #[test]
+    fn hash_changes_with_state() {
+        let mut arena = new_arena();
+        let before = hash(&mut arena);
+
+        arena.pin_mut().step(1);
+        assert_ne!(before, hash(&mut arena));
+    }
+
+    #[test]
+    fn hash_ignores_tick_count() {
+        let mut arena = new_arena();
+        let mut game_state = arena.pin_mut().get_game_state();
+        let before = state_hash(game_state.clone());
+
+        game_state.tick_count += 1000;
+        assert_eq!(before, state_hash(game_state));
+    }
+
+    #[test]
+    fn cloned_arenas_stay_in_lockstep() {
+        let mut arena = new_arena();
+        let mut a = arena.pin_mut().clone(false);
+        let mut b = arena.pin_mut().clone(false);
+
+        assert_lockstep(&mut a, &mut b, 600);
+    }
+
+    #[test]
+    fn reseeded_arenas_stay_in_lockstep() {
+        let mut arena = new_arena();
+        let mut a = reseed(&mut arena);
+        let mut b = reseed(&mut arena);
+
+        assert_lockstep(&mut a, &mut b, 600);
+    }
+
+    #[test]
+    fn reseeded_arena_matches_original() {
+        let mut arena = new_arena();
+        let mut seeded = reseed(&mut arena);
+
+        assert_lockstep(&mut arena, &mut seeded, 600);
+    }
+
+    #[test]
+    fn game_state_round_trip_keeps_hash() {
+        let mut arena = new_arena();
+        let before = hash(&mut arena);
+
+        let game_state = arena.pin_mut().get_game_state();
+        arena.pin_mut().set_game_state(&game_state).unwrap();
+        assert_eq!(before, hash(&mut arena));
+
+        let mut seeded = reseed(&mut arena);
+        assert_eq!(before, hash(&mut seeded));
+    }
+
+    #[test]
+    fn reseeding_mid_episode_stays_in_lockstep() {
+        let mut arena = new_arena();
+        let mut a = reseed(&mut arena);
+        let mut b = reseed(&mut arena);
+
+        assert_lockstep(&mut a, &mut b, 120);
+
+        // round trip both through the same game state part way through
+        let game_state = a.pin_mut().get_game_state();
+        a.pin_mut().set_game_state(&game_state).unwrap();
+        b.pin_mut().set_game_state(&game_state).unwrap();
+
+        assert_lockstep(&mut a, &mut b, 480);
+    }
+
+    #[test]
+    fn reseeding_mid_episode_matches_original() {
+        let mut arena = new_arena();
+        arena.pin_mut().step(120);
+
+        let mut seeded = reseed(&mut arena);
+        assert_lockstep(&mut arena, &mut seeded, 480);
+    }
+}
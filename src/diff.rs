@@ -0,0 +1,243 @@
+use std::{collections::HashMap, hash::Hash};
+
+use pyo3::{prelude::*, pyclass::CompareOp};
+use rocketsim_rs::{
+    glam_ext::glam::{Mat3A, Quat},
+    math::{RotMat as CRotMat, Vec3 as CVec3},
+    sim::{
+        BallHitInfo as CBallHitInfo, BallState, BoostPadState as CBoostPadState, CarConfig as CCarConfig, CarControls as CCarControls, CarState, Team,
+        WheelPairConfig as CWheelPairConfig,
+    },
+    CarInfo as CCarInfo, GameState as CGameState,
+};
+
+use crate::base::{finish_hash, hash_f32, richcmp_eq};
+
+/// The default tolerance for `diff` and `approx_eq`
+pub const DEFAULT_TOLERANCE: f32 = 1e-4;
+
+/// NaN only matches NaN
+#[inline]
+pub fn floats_differ(a: f32, b: f32, tolerance: f32) -> bool {
+    (a - b).abs() > tolerance || a.is_nan() != b.is_nan()
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    /// The path to the field, like `cars[1].state.boost`
+    pub field: String,
+    pub old: f32,
+    pub new: f32,
+}
+
+#[pymethods]
+impl FieldDiff {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{}: {} -> {}", self.field, self.old, self.new)
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!("FieldDiff(field={:?}, old={}, new={})", self.field, self.old, self.new)
+    }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    #[inline]
+    fn __hash__(&self) -> u64 {
+        finish_hash(|state| {
+            self.field.hash(state);
+            hash_f32(self.old, state);
+            hash_f32(self.new, state);
+        })
+    }
+}
+
+/// Collects the fields that differ by more than the tolerance, bools are compared as 0 or 1 and ids exactly
+///
+/// Tick counts are left out because `set_game_state` can't restore them
+pub struct Differ {
+    tolerance: f32,
+    /// Old car id -> new car id, for comparing fields that hold car ids when the cars were added in a different order
+    pub car_ids: HashMap<u32, u32>,
+    /// Whether `game_state` compares the ball's rotation, which `set_game_state` can't restore
+    pub ball_rot: bool,
+    pub diffs: Vec<FieldDiff>,
+}
+
+impl Differ {
+    #[inline]
+    pub fn new(tolerance: f32) -> Self {
+        Self {
+            tolerance,
+            car_ids: HashMap::new(),
+            ball_rot: true,
+            diffs: Vec::new(),
+        }
+    }
+
+    pub fn float(&mut self, prefix: &str, field: &str, old: f32, new: f32) {
+        if floats_differ(old, new, self.tolerance) {
+            self.diffs.push(FieldDiff {
+                field: format!("{prefix}.{field}"),
+                old,
+                new,
+            });
+        }
+    }
+
+    /// Ids are compared exactly, since ids a tolerance apart are still different cars
+    pub fn id(&mut self, prefix: &str, field: &str, old: u32, new: u32) {
+        if old != new {
+            self.diffs.push(FieldDiff {
+                field: format!("{prefix}.{field}"),
+                old: old as f32,
+                new: new as f32,
+            });
+        }
+    }
+
+    /// Like `id`, but the old id is first mapped to its new car with `car_ids`
+    #[inline]
+    pub fn car_id(&mut self, prefix: &str, field: &str, old: u32, new: u32) {
+        let old = self.car_ids.get(&old).copied().unwrap_or(old);
+        self.id(prefix, field, old, new);
+    }
+
+    #[inline]
+    pub fn bool(&mut self, prefix: &str, field: &str, old: bool, new: bool) {
+        self.float(prefix, field, f32::from(u8::from(old)), f32::from(u8::from(new)));
+    }
+
+    pub fn vec3(&mut self, prefix: &str, field: &str, old: CVec3, new: CVec3) {
+        let prefix = format!("{prefix}.{field}");
+        self.float(&prefix, "x", old.x, new.x);
+        self.float(&prefix, "y", old.y, new.y);
+        self.float(&prefix, "z", old.z, new.z);
+    }
+
+    pub fn rot_mat(&mut self, prefix: &str, field: &str, old: CRotMat, new: CRotMat) {
+        let prefix = format!("{prefix}.{field}");
+        self.vec3(&prefix, "forward", old.forward, new.forward);
+        self.vec3(&prefix, "right", old.right, new.right);
+        self.vec3(&prefix, "up", old.up, new.up);
+    }
+
+    pub fn ball(&mut self, prefix: &str, old: &BallState, new: &BallState) {
+        self.vec3(prefix, "pos", old.pos, new.pos);
+        self.vec3(prefix, "vel", old.vel, new.vel);
+        self.vec3(prefix, "ang_vel", old.ang_vel, new.ang_vel);
+    }
+
+    fn ball_hit_info(&mut self, prefix: &str, old: &CBallHitInfo, new: &CBallHitInfo) {
+        self.bool(prefix, "is_valid", old.is_valid, new.is_valid);
+        self.vec3(prefix, "relative_pos_on_ball", old.relative_pos_on_ball, new.relative_pos_on_ball);
+        self.vec3(prefix, "ball_pos", old.ball_pos, new.ball_pos);
+        self.vec3(prefix, "extra_hit_vel", old.extra_hit_vel, new.extra_hit_vel);
+    }
+
+    fn controls(&mut self, prefix: &str, old: &CCarControls, new: &CCarControls) {
+        self.float(prefix, "throttle", old.throttle, new.throttle);
+        self.float(prefix, "steer", old.steer, new.steer);
+        self.float(prefix, "pitch", old.pitch, new.pitch);
+        self.float(prefix, "yaw", old.yaw, new.yaw);
+        self.float(prefix, "roll", old.roll, new.roll);
+        self.bool(prefix, "jump", old.jump, new.jump);
+        self.bool(prefix, "boost", old.boost, new.boost);
+        self.bool(prefix, "handbrake", old.handbrake, new.handbrake);
+    }
+
+    fn wheel_pair_config(&mut self, prefix: &str, old: &CWheelPairConfig, new: &CWheelPairConfig) {
+        self.float(prefix, "wheel_radius", old.wheel_radius, new.wheel_radius);
+        self.float(prefix, "suspension_rest_length", old.suspension_rest_length, new.suspension_rest_length);
+        self.vec3(prefix, "connection_point_offset", old.connection_point_offset, new.connection_point_offset);
+    }
+
+    fn car_config(&mut self, prefix: &str, old: &CCarConfig, new: &CCarConfig) {
+        self.vec3(prefix, "hitbox_size", old.hitbox_size, new.hitbox_size);
+        self.vec3(prefix, "hitbox_pos_offset", old.hitbox_pos_offset, new.hitbox_pos_offset);
+        self.wheel_pair_config(&format!("{prefix}.front_wheels"), &old.front_wheels, &new.front_wheels);
+        self.wheel_pair_config(&format!("{prefix}.back_wheels"), &old.back_wheels, &new.back_wheels);
+        self.float(prefix, "dodge_deadzone", old.dodge_deadzone, new.dodge_deadzone);
+    }
+
+    fn pad(&mut self, prefix: &str, old: &CBoostPadState, new: &CBoostPadState) {
+        self.bool(prefix, "is_active", old.is_active, new.is_active);
+        self.float(prefix, "cooldown", old.cooldown, new.cooldown);
+        self.car_id(prefix, "cur_locked_car_id", old.cur_locked_car_id, new.cur_locked_car_id);
+        self.car_id(prefix, "prev_locked_car_id", old.prev_locked_car_id, new.prev_locked_car_id);
+    }
+
+    pub fn car(&mut self, prefix: &str, old: &CarState, new: &CarState) {
+        self.vec3(prefix, "pos", old.pos, new.pos);
+        self.vec3(prefix, "vel", old.vel, new.vel);
+        self.vec3(prefix, "ang_vel", old.ang_vel, new.ang_vel);
+        self.rot_mat(prefix, "rot_mat", old.rot_mat, new.rot_mat);
+        self.float(prefix, "boost", old.boost, new.boost);
+        self.bool(prefix, "is_demoed", old.is_demoed, new.is_demoed);
+        self.bool(prefix, "is_on_ground", old.is_on_ground, new.is_on_ground);
+        self.bool(prefix, "has_jumped", old.has_jumped, new.has_jumped);
+        self.bool(prefix, "has_double_jumped", old.has_double_jumped, new.has_double_jumped);
+        self.bool(prefix, "has_flipped", old.has_flipped, new.has_flipped);
+        self.vec3(prefix, "last_rel_dodge_torque", old.last_rel_dodge_torque, new.last_rel_dodge_torque);
+        self.float(prefix, "jump_time", old.jump_time, new.jump_time);
+        self.float(prefix, "flip_time", old.flip_time, new.flip_time);
+        self.bool(prefix, "is_jumping", old.is_jumping, new.is_jumping);
+        self.float(prefix, "air_time_since_jump", old.air_time_since_jump, new.air_time_since_jump);
+        self.float(prefix, "time_spent_boosting", old.time_spent_boosting, new.time_spent_boosting);
+        self.bool(prefix, "is_supersonic", old.is_supersonic, new.is_supersonic);
+        self.float(prefix, "supersonic_time", old.supersonic_time, new.supersonic_time);
+        self.float(prefix, "handbrake_val", old.handbrake_val, new.handbrake_val);
+        self.bool(prefix, "is_auto_flipping", old.is_auto_flipping, new.is_auto_flipping);
+        self.float(prefix, "auto_flip_timer", old.auto_flip_timer, new.auto_flip_timer);
+        self.float(prefix, "auto_flip_torque_scale", old.auto_flip_torque_scale, new.auto_flip_torque_scale);
+        self.bool(prefix, "has_contact", old.has_contact, new.has_contact);
+        self.vec3(prefix, "contact_normal", old.contact_normal, new.contact_normal);
+        self.car_id(prefix, "other_car_id", old.other_car_id, new.other_car_id);
+        self.float(prefix, "cooldown_timer", old.cooldown_timer, new.cooldown_timer);
+        self.float(prefix, "demo_respawn_timer", old.demo_respawn_timer, new.demo_respawn_timer);
+        self.ball_hit_info(&format!("{prefix}.ball_hit_info"), &old.ball_hit_info, &new.ball_hit_info);
+        self.controls(&format!("{prefix}.last_controls"), &old.last_controls, &new.last_controls);
+    }
+
+    /// Compares the ball and its rotation, then each pair of cars, then the pads
+    ///
+    /// `cars` pairs each old car with its name in the field paths and the new car it should match, if there is one
+    pub fn game_state<'a>(&mut self, old: &CGameState, new: &CGameState, cars: impl IntoIterator<Item = (String, &'a CCarInfo, Option<&'a CCarInfo>)>) {
+        self.ball("ball", &old.ball, &new.ball);
+        if self.ball_rot {
+            let [old_rot, new_rot] = [old.ball_rot, new.ball_rot].map(|rot| CRotMat::from(Mat3A::from_quat(Quat::from_array(rot))));
+            self.vec3("ball_rot", "forward", old_rot.forward, new_rot.forward);
+            self.vec3("ball_rot", "right", old_rot.right, new_rot.right);
+            self.vec3("ball_rot", "up", old_rot.up, new_rot.up);
+        }
+
+        if old.cars.len() != new.cars.len() {
+            self.float("cars", "len", old.cars.len() as f32, new.cars.len() as f32);
+        }
+
+        for (name, old_car, new_car) in cars {
+            let Some(new_car) = new_car else {
+                self.diffs.push(FieldDiff { field: name, old: 1., new: 0. });
+                continue;
+            };
+
+            self.bool(&name, "team", old_car.team != Team::BLUE, new_car.team != Team::BLUE);
+            self.car(&format!("{name}.state"), &old_car.state, &new_car.state);
+            self.car_config(&format!("{name}.config"), &old_car.config, &new_car.config);
+        }
+
+        if old.pads.len() != new.pads.len() {
+            self.float("pads", "len", old.pads.len() as f32, new.pads.len() as f32);
+        }
+
+        for (i, (old_pad, new_pad)) in old.pads.iter().zip(&new.pads).enumerate() {
+            self.pad(&format!("pads[{i}].state"), &old_pad.state, &new_pad.state);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use pyo3::{prelude::*, pyclass::CompareOp};
+use std::{f32::consts::SQRT_2, hash::Hash};
+
+use crate::{
+    base::{finish_hash, hash_f32, richcmp_eq, Vec3},
+    python::{BoostPadStatic, GameMode, Team},
+};
+
+pub const SOCCAR_EXTENT_X: f32 = 4096.;
+pub const SOCCAR_EXTENT_Y: f32 = 5120.;
+const SOCCAR_HEIGHT: f32 = 2048.;
+const SOCCAR_CORNER_OFFSET: f32 = 8064.;
+const SOCCAR_GOAL_WIDTH: f32 = 1786.;
+const SOCCAR_GOAL_HEIGHT: f32 = 642.775;
+pub const SOCCAR_GOAL_DEPTH: f32 = 880.;
+pub const SOCCAR_BALL_SCORE_THRESHOLD_Y: f32 = 5121.75 + 91.25;
+
+/// Same order as `RLConst::BoostPads::LOCS_BIG` and `LOCS_SMALL`, so the indices line up with `Arena.get_pad_static`
+const SOCCAR_BIG_PADS: [Vec3; 6] = [
+    Vec3::new(-3584., 0., 73.),
+    Vec3::new(3584., 0., 73.),
+    Vec3::new(-3072., 4096., 73.),
+    Vec3::new(3072., 4096., 73.),
+    Vec3::new(-3072., -4096., 73.),
+    Vec3::new(3072., -4096., 73.),
+];
+
+const SOCCAR_SMALL_PADS: [Vec3; 28] = [
+    Vec3::new(0., -4240., 70.),
+    Vec3::new(-1792., -4184., 70.),
+    Vec3::new(1792., -4184., 70.),
+    Vec3::new(-940., -3308., 70.),
+    Vec3::new(940., -3308., 70.),
+    Vec3::new(0., -2816., 70.),
+    Vec3::new(-3584., -2484., 70.),
+    Vec3::new(3584., -2484., 70.),
+    Vec3::new(-1788., -2300., 70.),
+    Vec3::new(1788., -2300., 70.),
+    Vec3::new(-2048., -1036., 70.),
+    Vec3::new(0., -1024., 70.),
+    Vec3::new(2048., -1036., 70.),
+    Vec3::new(-1024., 0., 70.),
+    Vec3::new(1024., 0., 70.),
+    Vec3::new(-2048., 1036., 70.),
+    Vec3::new(0., 1024., 70.),
+    Vec3::new(2048., 1036., 70.),
+    Vec3::new(-1788., 2300., 70.),
+    Vec3::new(1788., 2300., 70.),
+    Vec3::new(-3584., 2484., 70.),
+    Vec3::new(3584., 2484., 70.),
+    Vec3::new(0., 2816., 70.),
+    Vec3::new(-940., 3310., 70.),
+    Vec3::new(940., 3308., 70.),
+    Vec3::new(-1792., 4184., 70.),
+    Vec3::new(1792., 4184., 70.),
+    Vec3::new(0., 4240., 70.),
+];
+
+#[pyclass(get_all, frozen, module = "rocketsim.field")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Goal {
+    /// The team that defends this goal
+    pub team: Team,
+    /// The center of the goal mouth, on the goal line
+    pub center: Vec3,
+    pub width: f32,
+    pub height: f32,
+    pub depth: f32,
+}
+
+impl Goal {
+    /// The sign of the y axis that this goal is on
+    #[inline]
+    fn side(&self) -> f32 {
+        self.center.y.signum()
+    }
+
+    fn contains(&self, pos: Vec3) -> bool {
+        let behind_line = pos.y * self.side() - self.center.y.abs();
+        pos.x.abs() <= self.width / 2. && (0. ..=self.height).contains(&pos.z) && (0. ..=self.depth).contains(&behind_line)
+    }
+}
+
+#[pymethods]
+impl Goal {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!(
+            "Goal(team={}, center={}, width={}, height={}, depth={})",
+            self.team.__repr__(),
+            self.center.__repr__(),
+            self.width,
+            self.height,
+            self.depth
+        )
+    }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    #[inline]
+    fn __hash__(&self) -> u64 {
+        finish_hash(|state| {
+            self.team.hash(state);
+            self.center.hash_components(state);
+            hash_f32(self.width, state);
+            hash_f32(self.height, state);
+            hash_f32(self.depth, state);
+        })
+    }
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim.field")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub gamemode: GameMode,
+    /// Distance from the center to the side walls
+    pub extent_x: f32,
+    /// Distance from the center to the back walls, not including the goals
+    pub extent_y: f32,
+    /// Height of the ceiling
+    pub height: f32,
+    /// The corner walls are the planes where `|x| + |y|` equals this
+    pub corner_offset: f32,
+    /// The y distance from the center that the ball's center must pass to score
+    pub goal_score_threshold_y: f32,
+    /// The goals, blue then orange
+    pub goals: Vec<Goal>,
+    /// The boost pads, in the same order as `Arena.get_pad_static`
+    pub boost_pads: Vec<BoostPadStatic>,
+}
+
+impl Field {
+    fn goal(&self, team: Team) -> Option<Goal> {
+        self.goals.iter().find(|goal| goal.team == team).copied()
+    }
+}
+
+#[pymethods]
+impl Field {
+    #[inline]
+    #[staticmethod]
+    pub fn soccar() -> Self {
+        let goal = |team, side: f32| Goal {
+            team,
+            center: Vec3::new(0., SOCCAR_EXTENT_Y * side, SOCCAR_GOAL_HEIGHT / 2.),
+            width: SOCCAR_GOAL_WIDTH,
+            height: SOCCAR_GOAL_HEIGHT,
+            depth: SOCCAR_GOAL_DEPTH,
+        };
+
+        Self {
+            gamemode: GameMode::Soccar,
+            extent_x: SOCCAR_EXTENT_X,
+            extent_y: SOCCAR_EXTENT_Y,
+            height: SOCCAR_HEIGHT,
+            corner_offset: SOCCAR_CORNER_OFFSET,
+            goal_score_threshold_y: SOCCAR_BALL_SCORE_THRESHOLD_Y,
+            goals: vec![goal(Team::Blue, -1.), goal(Team::Orange, 1.)],
+            boost_pads: SOCCAR_BIG_PADS
+                .into_iter()
+                .map(|pos| BoostPadStatic { pos, is_big: true })
+                .chain(SOCCAR_SMALL_PADS.into_iter().map(|pos| BoostPadStatic { pos, is_big: false }))
+                .collect(),
+        }
+    }
+
+    /// An empty field without walls, goals or boost pads
+    #[inline]
+    #[staticmethod]
+    pub fn the_void() -> Self {
+        Self {
+            gamemode: GameMode::TheVoid,
+            extent_x: f32::INFINITY,
+            extent_y: f32::INFINITY,
+            height: f32::INFINITY,
+            corner_offset: f32::INFINITY,
+            goal_score_threshold_y: f32::INFINITY,
+            goals: Vec::new(),
+            boost_pads: Vec::new(),
+        }
+    }
+
+    #[inline]
+    #[staticmethod]
+    pub fn from_gamemode(gamemode: GameMode) -> Self {
+        match gamemode {
+            GameMode::Soccar => Self::soccar(),
+            GameMode::TheVoid => Self::the_void(),
+        }
+    }
+
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+/// Returns the constants for the given game mode
+#[pyfunction]
+#[inline]
+#[pyo3(signature = (gamemode=GameMode::Soccar))]
+pub fn get_field(gamemode: GameMode) -> Field {
+    Field::from_gamemode(gamemode)
+}
+
+/// Returns the goal that the given team defends, or None if the game mode doesn't have goals
+#[pyfunction]
+#[inline]
+#[pyo3(signature = (team, gamemode=GameMode::Soccar))]
+pub fn own_goal(team: Team, gamemode: GameMode) -> Option<Goal> {
+    Field::from_gamemode(gamemode).goal(team)
+}
+
+/// Returns the goal that the given team scores in, or None if the game mode doesn't have goals
+#[pyfunction]
+#[inline]
+#[pyo3(signature = (team, gamemode=GameMode::Soccar))]
+pub fn opponent_goal(team: Team, gamemode: GameMode) -> Option<Goal> {
+    let opponent = match team {
+        Team::Blue => Team::Orange,
+        Team::Orange => Team::Blue,
+    };
+
+    Field::from_gamemode(gamemode).goal(opponent)
+}
+
+/// Returns true if the position is inside the goal that the given team defends, behind the goal line
+#[pyfunction]
+#[inline]
+#[pyo3(signature = (pos, team, gamemode=GameMode::Soccar))]
+pub fn is_in_goal(pos: Vec3, team: Team, gamemode: GameMode) -> bool {
+    own_goal(team, gamemode).is_some_and(|goal| goal.contains(pos))
+}
+
+/// Returns the horizontal distance from the position to the closest side, back or corner wall
+///
+/// The goal openings are ignored and the result is negative outside of the field
+#[pyfunction]
+#[inline]
+#[pyo3(signature = (pos, gamemode=GameMode::Soccar))]
+pub fn distance_to_wall(pos: Vec3, gamemode: GameMode) -> f32 {
+    let field = Field::from_gamemode(gamemode);
+    let (x, y) = (pos.x.abs(), pos.y.abs());
+
+    (field.extent_x - x).min(field.extent_y - y).min((field.corner_offset - x - y) / SQRT_2)
+}
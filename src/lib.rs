@@ -1,11 +1,32 @@
 mod base;
+mod determinism;
+mod diff;
+mod field_info;
+mod meshes;
+mod packet;
+mod pool;
 mod python;
+mod render;
+mod replay;
+mod reward;
+mod server;
+mod setters;
 mod state;
+mod view;
 
 use base::*;
+use diff::*;
+use field_info::*;
+use meshes::*;
+use packet::*;
 use pyo3::prelude::*;
 use python::*;
+use replay::*;
+use reward::*;
+use server::*;
+use setters::*;
 use state::*;
+use view::*;
 
 macro_rules! pynamedmodule {
     (doc: $doc:literal, name: $name:tt, funcs: [$($func_name:path),*], classes: [$($class_name:ident),*], submodules: [$($submodule_name:ident),*]) => {
@@ -23,17 +44,49 @@ macro_rules! pynamedmodule {
 }
 
 pynamedmodule! {
-    doc: "",
+    doc: "The simulation types and the Arena",
     name: sim,
     funcs: [],
-    classes: [Arena, BoostPadState, BoostPadStatic, GameMode, Team, WheelPairConfig, CarConfig, Car, Ball, CarControls, BallHitInfo],
+    classes: [Arena, CarView, BallView, RayHit, CarObb, ArenaRecorder, ReplayReader, ReplayPlayer, ReplayDivergence, ReplayFrame, ReplayEvent, ReplayEventKind, BoostPadState, BoostPadStatic, GameMode, Team, WheelPairConfig, CarPreset, CarConfig, Car, Ball, CarControls, BallHitInfo],
     submodules: []
 }
 
 pynamedmodule! {
-    doc: "",
+    doc: "Constants and helpers for the field of each game mode",
+    name: field,
+    funcs: [get_field, own_goal, opponent_goal, is_in_goal, distance_to_wall],
+    classes: [Field, Goal],
+    submodules: []
+}
+
+pynamedmodule! {
+    doc: "Converters between GameState and RLBot's GameTickPacket and FieldInfo",
+    name: rlbot,
+    funcs: [game_state_to_packet, packet_to_game_state, get_field_info],
+    classes: [],
+    submodules: []
+}
+
+pynamedmodule! {
+    doc: "Seeded generators that put an Arena in random but valid states for training",
+    name: state_setters,
+    funcs: [random_ball, random_cars, random_boost, random_state, kickoff, goalie],
+    classes: [],
+    submodules: []
+}
+
+pynamedmodule! {
+    doc: "Reward functions for training, computed from the state of an Arena",
+    name: rewards,
+    funcs: [],
+    classes: [RewardTerm, RewardFunction],
+    submodules: []
+}
+
+pynamedmodule! {
+    doc: "Python bindings for the RocketSim project",
     name: rocketsim,
-    funcs: [init],
-    classes: [Vec3, RotMat, GameState, GameState, CarInfo, BoostPad],
-    submodules: [sim]
+    funcs: [init, init_from_temp_folder, is_initialized, serve],
+    classes: [Vec3, RotMat, GameState, GameState, CarInfo, BoostPad, InitInfo, FieldDiff],
+    submodules: [sim, field, rlbot, state_setters, rewards]
 }
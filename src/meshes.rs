@@ -0,0 +1,593 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use pyo3::{
+    exceptions::{PyFileNotFoundError, PyRuntimeError, PyValueError},
+    prelude::*,
+};
+use rocketsim_rs::{get_stage, glam_ext::glam::Vec3 as GVec3, Stages};
+
+use crate::python::GameMode;
+
+const COLLISION_MESH_FILE_EXTENSION: &str = "cmf";
+const MAX_VERT_OR_TRI_COUNT: i32 = 1000 * 1000;
+
+const SOCCAR_FOLDER: &str = "soccar";
+const SOCCAR_ARENA_MESH_HASHES: [u32; 16] = [
+    0xA160BAF9, 0x2811EEE8, 0xB81AC8B9, 0x760358D3, 0x73AE4940, 0x918F4A4E, 0x1F8EE550, 0x255BA8C1, 0x14B84668, 0xEC759EBF, 0x94FB0D5C, 0xDEA07102, 0xBD4FBEA8, 0x39A47F63,
+    0x3D79D25D, 0xD84C7A68,
+];
+
+const BT_TO_UU: f32 = 50.;
+const ARENA_EXTENT_X: f32 = 4096.;
+const ARENA_HEIGHT: f32 = 2048.;
+
+static INIT_INFO: Mutex<Option<InitInfo>> = Mutex::new(None);
+/// Built from the soccar meshes once they're loaded
+static ARENA_BVH: OnceLock<Bvh> = OnceLock::new();
+
+/// A triangle in UU, with the edges and normal precomputed for ray tests
+struct Triangle {
+    v0: GVec3,
+    edge1: GVec3,
+    edge2: GVec3,
+    normal: GVec3,
+}
+
+impl Triangle {
+    /// Möller–Trumbore, returning the distance to the hit
+    fn raycast(&self, origin: GVec3, dir: GVec3, max_dist: f32) -> Option<f32> {
+        let p = dir.cross(self.edge2);
+        let det = self.edge1.dot(p);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let inv_det = 1. / det;
+        let s = origin - self.v0;
+        let u = s.dot(p) * inv_det;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(self.edge1);
+        let v = dir.dot(q) * inv_det;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+
+        let dist = self.edge2.dot(q) * inv_det;
+        (0. ..=max_dist).contains(&dist).then_some(dist)
+    }
+
+    #[inline]
+    fn min(&self) -> GVec3 {
+        self.v0.min(self.v0 + self.edge1).min(self.v0 + self.edge2)
+    }
+
+    #[inline]
+    fn max(&self) -> GVec3 {
+        self.v0.max(self.v0 + self.edge1).max(self.v0 + self.edge2)
+    }
+
+    #[inline]
+    fn centroid(&self) -> GVec3 {
+        self.v0 + (self.edge1 + self.edge2) / 3.
+    }
+}
+
+/// A validated `.cmf` collision mesh file
+pub struct CollisionMesh {
+    pub hash: u32,
+    triangles: Vec<Triangle>,
+}
+
+impl CollisionMesh {
+    /// Parses and validates a mesh the same way `CollisionMeshFile::ReadFromFile` does,
+    /// but returns an error instead of closing the process
+    pub fn from_bytes(name: &str, bytes: &[u8]) -> PyResult<Self> {
+        let read_i32 = |i: usize| i32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let read_f32 = |i: usize| f32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+        if bytes.len() < 8 {
+            return Err(PyValueError::new_err(format!("Invalid collision mesh file \"{name}\" (missing triangle/vertex count)")));
+        }
+
+        let (num_tris, num_vertices) = (read_i32(0), read_i32(4));
+        if num_tris.min(num_vertices) <= 0 || num_tris.max(num_vertices) > MAX_VERT_OR_TRI_COUNT {
+            return Err(PyValueError::new_err(format!(
+                "Invalid collision mesh file \"{name}\" (bad triangle/vertex count: [{num_tris}, {num_vertices}])"
+            )));
+        }
+
+        let tris_start = 8;
+        let vertices_start = tris_start + num_tris as usize * 12;
+        let expected_len = vertices_start + num_vertices as usize * 12;
+        if bytes.len() < expected_len {
+            return Err(PyValueError::new_err(format!(
+                "Invalid collision mesh file \"{name}\" (expected {expected_len} bytes, found {})",
+                bytes.len()
+            )));
+        }
+
+        let tris = (0..num_tris as usize)
+            .map(|i| tris_start + i * 12)
+            .map(|i| [read_i32(i), read_i32(i + 4), read_i32(i + 8)])
+            .collect::<Vec<_>>();
+
+        if tris.iter().flatten().any(|&index| index < 0 || index >= num_vertices) {
+            return Err(PyValueError::new_err(format!("Invalid collision mesh file \"{name}\" (bad triangle vertex index)")));
+        }
+
+        let vertices = (0..num_vertices as usize)
+            .map(|i| vertices_start + i * 12)
+            .map(|i| [read_f32(i), read_f32(i + 4), read_f32(i + 8)])
+            .collect::<Vec<_>>();
+
+        let points = vertices.iter().map(|&vertex| GVec3::from_array(vertex) * BT_TO_UU).collect::<Vec<_>>();
+        let triangles = tris
+            .iter()
+            .map(|tri| tri.map(|index| points[index as usize]))
+            .filter_map(|[v0, v1, v2]| {
+                let (edge1, edge2) = (v1 - v0, v2 - v0);
+                // degenerate triangles can't be hit
+                let normal = edge1.cross(edge2).try_normalize()?;
+                Some(Triangle { v0, edge1, edge2, normal })
+            })
+            .collect();
+
+        Ok(Self {
+            hash: mesh_hash(&tris, &vertices),
+            triangles,
+        })
+    }
+}
+
+/// How many triangles a BVH leaf holds before it's split
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+struct BvhNode {
+    min: GVec3,
+    max: GVec3,
+    /// For leaves, the range of `Bvh::triangles` they hold,
+    /// otherwise the index of the second child in `start` (the first child is the next node) and an empty range
+    start: usize,
+    end: usize,
+}
+
+impl BvhNode {
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.end > self.start
+    }
+
+    /// Slab test against the node's bounding box, returning the distance the ray enters it at
+    fn ray_entry(&self, origin: GVec3, inv_dir: GVec3, max_dist: f32) -> Option<f32> {
+        let t1 = (self.min - origin) * inv_dir;
+        let t2 = (self.max - origin) * inv_dir;
+        let t_min = t1.min(t2).max_element().max(0.);
+        let t_max = t1.max(t2).min_element().min(max_dist);
+        (t_min <= t_max).then_some(t_min)
+    }
+}
+
+/// A bounding volume hierarchy over every triangle of the arena meshes, built once when they're loaded
+struct Bvh {
+    nodes: Vec<BvhNode>,
+    triangles: Vec<Triangle>,
+}
+
+impl Bvh {
+    fn new(triangles: Vec<Triangle>) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(triangles.len() / MAX_LEAF_TRIANGLES * 2 + 1),
+            triangles,
+        };
+
+        if !bvh.triangles.is_empty() {
+            bvh.build(0, bvh.triangles.len());
+        }
+
+        bvh
+    }
+
+    /// Adds the node for `triangles[start..end]` and its children, splitting at the median of the longest axis
+    fn build(&mut self, start: usize, end: usize) {
+        let tris = &mut self.triangles[start..end];
+        let min = tris.iter().map(Triangle::min).fold(GVec3::splat(f32::INFINITY), GVec3::min);
+        let max = tris.iter().map(Triangle::max).fold(GVec3::splat(f32::NEG_INFINITY), GVec3::max);
+
+        let index = self.nodes.len();
+        self.nodes.push(BvhNode { min, max, start, end });
+
+        if tris.len() <= MAX_LEAF_TRIANGLES {
+            return;
+        }
+
+        let extent = max - min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = tris.len() / 2;
+        tris.select_nth_unstable_by(mid, |a, b| a.centroid()[axis].total_cmp(&b.centroid()[axis]));
+
+        self.build(start, start + mid);
+        let second = self.nodes.len();
+        self.build(start + mid, end);
+
+        self.nodes[index].start = second;
+        self.nodes[index].end = 0;
+    }
+
+    /// The distance and normal of the closest triangle hit within `max_dist`
+    fn raycast(&self, origin: GVec3, dir: GVec3, max_dist: f32) -> Option<(f32, GVec3)> {
+        let root = self.nodes.first()?;
+
+        let inv_dir = dir.recip();
+        let mut closest = None;
+        let mut closest_dist = max_dist;
+
+        let mut stack = Vec::with_capacity(64);
+        if root.ray_entry(origin, inv_dir, closest_dist).is_some() {
+            stack.push(0);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            if node.is_leaf() {
+                for tri in &self.triangles[node.start..node.end] {
+                    if let Some(dist) = tri.raycast(origin, dir, closest_dist) {
+                        closest_dist = dist;
+                        closest = Some((dist, tri.normal));
+                    }
+                }
+
+                continue;
+            }
+
+            let children = [index + 1, node.start].map(|child| (child, self.nodes[child].ray_entry(origin, inv_dir, closest_dist)));
+            let [near, far] = if children[1].1 < children[0].1 { [children[1], children[0]] } else { children };
+
+            // the nearer child is popped first, so its hits can prune the farther one
+            for (child, entry) in [far, near] {
+                if entry.is_some() {
+                    stack.push(child);
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+/// Port of `CollisionMeshFile::UpdateHash`, used to tell which of the known meshes were found
+fn mesh_hash(tris: &[[i32; 3]], vertices: &[[f32; 3]]) -> u32 {
+    const HASH_VAL_MUELLER: u32 = 0x45D9F3B;
+    const HASH_VAL_SHIFT: u32 = 0x9E3779B9;
+
+    let mut hash = (vertices.len() + tris.len() * vertices.len()) as u32;
+
+    for tri in tris {
+        for &index in tri {
+            for &val in &vertices[index as usize] {
+                // matches the truncating float -> uint32_t conversion in C++
+                let mut cur_val = val as i64 as u32;
+
+                for _ in 0..2 {
+                    cur_val = ((cur_val >> 16) ^ cur_val).wrapping_mul(HASH_VAL_MUELLER);
+                }
+
+                cur_val = (cur_val >> 16) ^ cur_val;
+                hash ^= cur_val.wrapping_add(HASH_VAL_SHIFT).wrapping_add(hash << 6).wrapping_add(hash >> 2);
+            }
+        }
+    }
+
+    hash
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim")]
+#[derive(Clone, Debug)]
+pub struct InitInfo {
+    /// The collision meshes folder that was loaded
+    folder: String,
+    /// The number of collision meshes loaded for each game mode folder
+    meshes_loaded: HashMap<String, usize>,
+    /// The hashes of the known arena meshes that weren't found
+    missing: Vec<String>,
+    /// The files that didn't match any known arena mesh
+    unknown_files: Vec<String>,
+}
+
+impl InitInfo {
+    fn new(folder: String, meshes: &[(String, CollisionMesh)]) -> Self {
+        Self {
+            folder,
+            meshes_loaded: HashMap::from([(SOCCAR_FOLDER.to_string(), meshes.len())]),
+            missing: SOCCAR_ARENA_MESH_HASHES
+                .iter()
+                .filter(|&&hash| !meshes.iter().any(|(_, mesh)| mesh.hash == hash))
+                .map(|hash| format!("{hash:#010X}"))
+                .collect(),
+            unknown_files: meshes
+                .iter()
+                .filter(|(_, mesh)| !SOCCAR_ARENA_MESH_HASHES.contains(&mesh.hash))
+                .map(|(name, _)| name.clone())
+                .collect(),
+        }
+    }
+}
+
+#[pymethods]
+impl InitInfo {
+    /// True if every known arena mesh was found
+    #[getter]
+    #[inline]
+    fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    fn __repr__(&self) -> String {
+        format!(
+            "InitInfo(folder={:?}, meshes_loaded={:?}, missing={:?}, unknown_files={:?})",
+            self.folder, self.meshes_loaded, self.missing, self.unknown_files
+        )
+    }
+}
+
+fn is_mesh_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == COLLISION_MESH_FILE_EXTENSION)
+}
+
+fn check_any_meshes(meshes: &[(String, CollisionMesh)], source: &str) -> PyResult<()> {
+    if meshes.is_empty() {
+        return Err(PyFileNotFoundError::new_err(format!(
+            "Failed to find soccar field asset files in {source}, there are no collision mesh files"
+        )));
+    }
+
+    Ok(())
+}
+
+fn read_mesh_folder(folder: &Path) -> PyResult<Vec<(String, CollisionMesh)>> {
+    let soccar_folder = folder.join(SOCCAR_FOLDER);
+    if !soccar_folder.is_dir() {
+        return Err(PyFileNotFoundError::new_err(format!(
+            "Failed to find arena collision mesh files at {}",
+            soccar_folder.display()
+        )));
+    }
+
+    let mut meshes = Vec::new();
+    for entry in fs::read_dir(&soccar_folder)? {
+        let path = entry?.path();
+        if is_mesh_file(&path) {
+            let name = path.display().to_string();
+            let mesh = CollisionMesh::from_bytes(&name, &fs::read(&path)?)?;
+            meshes.push((name, mesh));
+        }
+    }
+
+    check_any_meshes(&meshes, &soccar_folder.display().to_string())?;
+    Ok(meshes)
+}
+
+fn store_arena_meshes(meshes: Vec<(String, CollisionMesh)>) {
+    let triangles = meshes.into_iter().flat_map(|(_, mesh)| mesh.triangles).collect();
+    // only called once, while `INIT_INFO` is locked and unset
+    let _ = ARENA_BVH.set(Bvh::new(triangles));
+}
+
+/// Loads the collision meshes, raising an error if they're missing or invalid
+///
+/// Calling this again after a successful init returns the original result, the meshes can only be loaded once
+#[pyfunction]
+#[pyo3(signature = (collision_meshes_folder=None))]
+pub fn init(collision_meshes_folder: Option<&str>) -> PyResult<InitInfo> {
+    let mut init_info = INIT_INFO.lock().unwrap();
+    if let Some(info) = init_info.as_ref() {
+        return Ok(info.clone());
+    }
+
+    let folder = collision_meshes_folder.unwrap_or("collision_meshes");
+    let meshes = read_mesh_folder(Path::new(folder))?;
+
+    rocketsim_rs::init(Some(folder));
+
+    let info = InitInfo::new(folder.to_string(), &meshes);
+    store_arena_meshes(meshes);
+    Ok(init_info.insert(info).clone())
+}
+
+/// Creates a new, empty temporary folder that no other init attempt uses
+fn create_unique_temp_folder() -> io::Result<PathBuf> {
+    static ATTEMPT: AtomicU32 = AtomicU32::new(0);
+
+    loop {
+        let attempt = ATTEMPT.fetch_add(1, Ordering::Relaxed);
+        let folder = env::temp_dir().join(format!("rocketsim_collision_meshes_{}_{attempt}", process::id()));
+
+        // `create_dir` fails if the folder exists, so leftovers from another process or a failed attempt are never reused
+        match fs::create_dir(&folder) {
+            Ok(()) => return Ok(folder),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn write_mesh_folder(folder: &Path, files: &[&[u8]]) -> io::Result<()> {
+    let soccar_folder = folder.join(SOCCAR_FOLDER);
+    fs::create_dir(&soccar_folder)?;
+
+    // the given paths aren't trusted, so the files get new names
+    for (i, bytes) in files.iter().enumerate() {
+        fs::write(soccar_folder.join(format!("mesh_{i}.{COLLISION_MESH_FILE_EXTENSION}")), bytes)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the collision meshes from a dict of `path -> bytes` to a temporary folder and loads them from there, with the same validation as `init`
+///
+/// Paths are relative to the collision meshes folder, like `soccar/mesh_0.cmf`
+///
+/// RocketSim can only read meshes from a folder, so this needs a writable temp directory and raises an OSError without one.
+/// The folder is removed after loading.
+#[pyfunction]
+pub fn init_from_temp_folder(collision_meshes: HashMap<String, &[u8]>) -> PyResult<InitInfo> {
+    let mut init_info = INIT_INFO.lock().unwrap();
+    if let Some(info) = init_info.as_ref() {
+        return Ok(info.clone());
+    }
+
+    let mut meshes = Vec::new();
+    let mut files = Vec::new();
+    for (name, bytes) in &collision_meshes {
+        let path = Path::new(name);
+        if is_mesh_file(path) && path.parent().and_then(Path::file_name).is_some_and(|parent| parent == SOCCAR_FOLDER) {
+            meshes.push((name.clone(), CollisionMesh::from_bytes(name, bytes)?));
+            files.push(*bytes);
+        }
+    }
+
+    check_any_meshes(&meshes, "the given bytes")?;
+
+    let folder = create_unique_temp_folder()?;
+    if let Err(e) = write_mesh_folder(&folder, &files) {
+        let _ = fs::remove_dir_all(&folder);
+        return Err(e.into());
+    }
+
+    rocketsim_rs::init(Some(&folder.to_string_lossy()));
+
+    // RocketSim is initialized now, so failing to clean up mustn't fail the init
+    let _ = fs::remove_dir_all(&folder);
+
+    let info = InitInfo::new("<bytes>".to_string(), &meshes);
+    store_arena_meshes(meshes);
+    Ok(init_info.insert(info).clone())
+}
+
+/// Returns true if the collision meshes have been loaded
+#[pyfunction]
+#[inline]
+pub fn is_initialized() -> bool {
+    matches!(get_stage(), Stages::INITIALIZED)
+}
+
+/// Errors if the collision meshes needed for the given game mode haven't been loaded
+pub fn check_initialized(gamemode: GameMode) -> PyResult<()> {
+    if !is_initialized() {
+        return Err(PyRuntimeError::new_err(
+            "RocketSim has not been initialized, call rocketsim.init() with the path to your collision meshes folder first",
+        ));
+    }
+
+    if let GameMode::Soccar = gamemode {
+        let init_info = INIT_INFO.lock().unwrap();
+        if init_info.as_ref().map_or(0, |info| info.meshes_loaded[SOCCAR_FOLDER]) == 0 {
+            return Err(PyRuntimeError::new_err("No soccar collision meshes have been loaded, can't create a Soccar arena"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Casts a ray against the soccar arena's collision meshes and the floor, ceiling and side wall planes
+///
+/// `dir` must be normalized, the returned normal always faces back towards the ray
+pub fn raycast_soccar(origin: GVec3, dir: GVec3, max_dist: f32) -> Option<(GVec3, GVec3, f32)> {
+    // the planes are one-sided, like Bullet's static plane shapes
+    let planes = [(GVec3::Z, 0.), (GVec3::NEG_Z, -ARENA_HEIGHT), (GVec3::X, -ARENA_EXTENT_X), (GVec3::NEG_X, -ARENA_EXTENT_X)];
+
+    let mut closest = planes
+        .into_iter()
+        .filter_map(|(normal, offset)| {
+            let denom = normal.dot(dir);
+            if denom >= 0. {
+                return None;
+            }
+
+            let dist = (offset - normal.dot(origin)) / denom;
+            (0. ..=max_dist).contains(&dist).then_some((dist, normal))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+    if let Some(bvh) = ARENA_BVH.get() {
+        if let Some(hit) = bvh.raycast(origin, dir, closest.map_or(max_dist, |(dist, _)| dist)) {
+            closest = Some(hit);
+        }
+    }
+
+    closest.map(|(dist, normal)| {
+        let normal = if normal.dot(dir) > 0. { -normal } else { normal };
+        (origin + dir * dist, normal, dist)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketsim_rs::glam_ext::glam::Vec3 as GVec3;
+
+    use super::{Bvh, Triangle};
+
+    /// A bumpy grid of triangles, so the BVH has to split on every axis
+    fn terrain() -> Vec<Triangle> {
+        let height = |x: i32, y: i32| ((x * 7 + y * 13) % 5) as f32 * 40.;
+        let point = |x: i32, y: i32| GVec3::new(x as f32 * 100., y as f32 * 100., height(x, y));
+
+        (-20..20)
+            .flat_map(|x| (-20..20).map(move |y| (x, y)))
+            .flat_map(|(x, y)| [[point(x, y), point(x + 1, y), point(x + 1, y + 1)], [point(x, y), point(x + 1, y + 1), point(x, y + 1)]])
+            .map(|[v0, v1, v2]| {
+                let (edge1, edge2) = (v1 - v0, v2 - v0);
+                Triangle {
+                    v0,
+                    edge1,
+                    edge2,
+                    normal: edge1.cross(edge2).normalize(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        let bvh = Bvh::new(terrain());
+        let brute_force = terrain();
+
+        for i in 0..500 {
+            let t = i as f32;
+            let origin = GVec3::new((t * 37.).sin() * 1900., (t * 53.).cos() * 1900., 300. + (t * 11.).sin() * 250.);
+            let dir = GVec3::new((t * 3.).cos(), (t * 5.).sin(), -0.2 - (t * 7.).sin().abs()).normalize();
+
+            let expected = brute_force
+                .iter()
+                .filter_map(|tri| tri.raycast(origin, dir, 5000.).map(|dist| (dist, tri.normal)))
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+
+            assert_eq!(bvh.raycast(origin, dir, 5000.), expected, "ray {i} from {origin} towards {dir}");
+        }
+    }
+}
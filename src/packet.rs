@@ -0,0 +1,334 @@
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use rocketsim_rs::{
+    glam_ext::glam::{Mat3A, Quat},
+    math::{Angle, RotMat as CRotMat, Vec3 as CVec3},
+};
+
+use crate::{
+    base::IntoGil,
+    field_info::Field,
+    python::{GameMode, Team},
+    state::GameState,
+};
+
+pub const BALL_RADIUS: f32 = 91.25;
+const GRAVITY_Z: f32 = -650.;
+const BOOST_PAD_COOLDOWN_BIG: f32 = 10.;
+const BOOST_PAD_COOLDOWN_SMALL: f32 = 4.;
+
+/// The x and y of each soccar boost pad, in RLBot's `FieldInfo` order
+const RLBOT_PADS: [(f32, f32); 34] = [
+    (0., -4240.),
+    (-1792., -4184.),
+    (1792., -4184.),
+    (-3072., -4096.),
+    (3072., -4096.),
+    (-940., -3308.),
+    (940., -3308.),
+    (0., -2816.),
+    (-3584., -2484.),
+    (3584., -2484.),
+    (-1788., -2300.),
+    (1788., -2300.),
+    (-2048., -1036.),
+    (0., -1024.),
+    (2048., -1036.),
+    (-3584., 0.),
+    (-1024., 0.),
+    (1024., 0.),
+    (3584., 0.),
+    (-2048., 1036.),
+    (0., 1024.),
+    (2048., 1036.),
+    (-1788., 2300.),
+    (1788., 2300.),
+    (-3584., 2484.),
+    (3584., 2484.),
+    (0., 2816.),
+    (-940., 3310.),
+    (940., 3308.),
+    (-3072., 4096.),
+    (3072., 4096.),
+    (-1792., 4184.),
+    (1792., 4184.),
+    (0., 4240.),
+];
+
+/// The arena pad indices in RLBot's order, matching each of `RLBOT_PADS` to the closest pad, or none if the field has no pads
+fn rlbot_pad_order(positions: impl Iterator<Item = (f32, f32)>) -> Vec<usize> {
+    let positions = positions.collect::<Vec<_>>();
+    if positions.is_empty() {
+        return Vec::new();
+    }
+
+    let distance = |(x, y): (f32, f32), (rx, ry): (f32, f32)| (x - rx).hypot(y - ry);
+    RLBOT_PADS
+        .iter()
+        .map(|&rlbot_pos| {
+            (0..positions.len())
+                .min_by(|&a, &b| distance(positions[a], rlbot_pos).total_cmp(&distance(positions[b], rlbot_pos)))
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Reads a field from a dict, or an attribute from an object like RLBot's ctypes structs
+fn field<'py>(obj: &'py PyAny, name: &str) -> PyResult<&'py PyAny> {
+    match obj.downcast::<PyDict>() {
+        Ok(dict) => dict.get_item(name).ok_or_else(|| PyValueError::new_err(format!("Missing packet field \"{name}\""))),
+        Err(_) => obj.getattr(name),
+    }
+}
+
+fn extract<'py, T: FromPyObject<'py>>(obj: &'py PyAny, name: &str) -> PyResult<T> {
+    field(obj, name)?.extract()
+}
+
+fn vec3_to_dict(py: Python<'_>, vec: CVec3) -> PyResult<&PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("x", vec.x)?;
+    dict.set_item("y", vec.y)?;
+    dict.set_item("z", vec.z)?;
+    Ok(dict)
+}
+
+fn vec3_from_packet(obj: &PyAny) -> PyResult<CVec3> {
+    Ok(CVec3::new(extract(obj, "x")?, extract(obj, "y")?, extract(obj, "z")?))
+}
+
+fn physics_to_dict(py: Python<'_>, pos: CVec3, rot_mat: CRotMat, vel: CVec3, ang_vel: CVec3) -> PyResult<&PyDict> {
+    let angle = Angle::from(Quat::from_mat3a(&Mat3A::from(rot_mat)));
+
+    let rotation = PyDict::new(py);
+    rotation.set_item("pitch", angle.pitch)?;
+    rotation.set_item("yaw", angle.yaw)?;
+    rotation.set_item("roll", angle.roll)?;
+
+    let physics = PyDict::new(py);
+    physics.set_item("location", vec3_to_dict(py, pos)?)?;
+    physics.set_item("rotation", rotation)?;
+    physics.set_item("velocity", vec3_to_dict(py, vel)?)?;
+    physics.set_item("angular_velocity", vec3_to_dict(py, ang_vel)?)?;
+    Ok(physics)
+}
+
+fn rot_mat_from_packet(obj: &PyAny) -> PyResult<CRotMat> {
+    Ok(CRotMat::from(Angle {
+        pitch: extract(obj, "pitch")?,
+        yaw: extract(obj, "yaw")?,
+        roll: extract(obj, "roll")?,
+    }))
+}
+
+fn pad_cooldown(is_big: bool) -> f32 {
+    if is_big {
+        BOOST_PAD_COOLDOWN_BIG
+    } else {
+        BOOST_PAD_COOLDOWN_SMALL
+    }
+}
+
+/// Converts a `GameState` into a dict with the same shape and field names as RLBot's `GameTickPacket`
+///
+/// Cars are in the same order as `game_state.cars`, and boost pads are in RLBot's `FieldInfo` order
+#[pyfunction]
+pub fn game_state_to_packet<'py>(py: Python<'py>, game_state: &GameState) -> PyResult<&'py PyDict> {
+    let game_state = game_state.remove_gil_with_pads(py);
+
+    let game_cars = PyList::empty(py);
+    for car in &game_state.cars {
+        let state = &car.state;
+
+        let hitbox = PyDict::new(py);
+        hitbox.set_item("length", car.config.hitbox_size.x)?;
+        hitbox.set_item("width", car.config.hitbox_size.y)?;
+        hitbox.set_item("height", car.config.hitbox_size.z)?;
+
+        let packet_car = PyDict::new(py);
+        packet_car.set_item("physics", physics_to_dict(py, state.pos, state.rot_mat, state.vel, state.ang_vel)?)?;
+        packet_car.set_item("is_demolished", state.is_demoed)?;
+        packet_car.set_item("has_wheel_contact", state.is_on_ground)?;
+        packet_car.set_item("is_super_sonic", state.is_supersonic)?;
+        packet_car.set_item("is_bot", true)?;
+        packet_car.set_item("jumped", state.has_jumped)?;
+        packet_car.set_item("double_jumped", state.has_double_jumped)?;
+        packet_car.set_item("name", format!("Car {}", car.id))?;
+        packet_car.set_item("team", Team::from(car.team) as u8)?;
+        packet_car.set_item("boost", state.boost)?;
+        packet_car.set_item("hitbox", hitbox)?;
+        packet_car.set_item("hitbox_offset", vec3_to_dict(py, car.config.hitbox_pos_offset)?)?;
+        packet_car.set_item("spawn_id", car.id)?;
+        game_cars.append(packet_car)?;
+    }
+
+    let game_boosts = PyList::empty(py);
+    for i in rlbot_pad_order(game_state.pads.iter().map(|pad| (pad.position.x, pad.position.y))) {
+        let pad = &game_state.pads[i];
+
+        let packet_pad = PyDict::new(py);
+        packet_pad.set_item("is_active", pad.state.is_active)?;
+        // RLBot counts up from when the pad was picked up, RocketSim counts down until it's active again
+        packet_pad.set_item("timer", if pad.state.is_active { 0. } else { pad_cooldown(pad.is_big) - pad.state.cooldown })?;
+        game_boosts.append(packet_pad)?;
+    }
+
+    let latest_touch = PyDict::new(py);
+    let last_touch = game_state
+        .cars
+        .iter()
+        .enumerate()
+        .filter(|(_, car)| car.state.ball_hit_info.is_valid)
+        .max_by_key(|(_, car)| car.state.ball_hit_info.tick_count_when_hit);
+    if let Some((player_index, car)) = last_touch {
+        let hit = &car.state.ball_hit_info;
+        let rel_pos = hit.relative_pos_on_ball;
+        let length = (rel_pos.x * rel_pos.x + rel_pos.y * rel_pos.y + rel_pos.z * rel_pos.z).sqrt().max(f32::EPSILON);
+
+        latest_touch.set_item("player_name", format!("Car {}", car.id))?;
+        latest_touch.set_item("time_seconds", hit.tick_count_when_hit as f32 / game_state.tick_rate)?;
+        latest_touch.set_item(
+            "hit_location",
+            vec3_to_dict(py, CVec3::new(hit.ball_pos.x + rel_pos.x, hit.ball_pos.y + rel_pos.y, hit.ball_pos.z + rel_pos.z))?,
+        )?;
+        latest_touch.set_item("hit_normal", vec3_to_dict(py, CVec3::new(rel_pos.x / length, rel_pos.y / length, rel_pos.z / length))?)?;
+        latest_touch.set_item("team", Team::from(car.team) as u8)?;
+        latest_touch.set_item("player_index", player_index)?;
+    }
+
+    let sphere = PyDict::new(py);
+    sphere.set_item("diameter", BALL_RADIUS * 2.)?;
+    let collision_shape = PyDict::new(py);
+    collision_shape.set_item("type", 1)?;
+    collision_shape.set_item("sphere", sphere)?;
+
+    let ball = &game_state.ball;
+    let ball_rot_mat = CRotMat::from(Mat3A::from_quat(Quat::from_array(game_state.ball_rot)));
+    let game_ball = PyDict::new(py);
+    game_ball.set_item("physics", physics_to_dict(py, ball.pos, ball_rot_mat, ball.vel, ball.ang_vel)?)?;
+    game_ball.set_item("latest_touch", latest_touch)?;
+    game_ball.set_item("collision_shape", collision_shape)?;
+
+    let game_info = PyDict::new(py);
+    game_info.set_item("seconds_elapsed", game_state.tick_count as f32 / game_state.tick_rate)?;
+    game_info.set_item("game_time_remaining", 0.)?;
+    game_info.set_item("is_overtime", false)?;
+    game_info.set_item("is_unlimited_time", true)?;
+    game_info.set_item("is_round_active", true)?;
+    game_info.set_item("is_kickoff_pause", false)?;
+    game_info.set_item("is_match_ended", false)?;
+    game_info.set_item("world_gravity_z", GRAVITY_Z)?;
+    game_info.set_item("game_speed", 1.)?;
+    game_info.set_item("frame_num", game_state.tick_count)?;
+
+    let packet = PyDict::new(py);
+    packet.set_item("num_cars", game_cars.len())?;
+    packet.set_item("game_cars", game_cars)?;
+    packet.set_item("num_boosts", game_boosts.len())?;
+    packet.set_item("game_boosts", game_boosts)?;
+    packet.set_item("game_ball", game_ball)?;
+    packet.set_item("game_info", game_info)?;
+    Ok(packet)
+}
+
+/// Applies a `GameTickPacket` shaped dict (or RLBot's own `GameTickPacket`) to a copy of the given `GameState`
+///
+/// The game state provides what the packet doesn't have, like the car ids and configs.
+/// Its cars are matched to the packet's cars by index, and the pads are mapped from RLBot's `FieldInfo` order.
+#[pyfunction]
+pub fn packet_to_game_state(py: Python, packet: &PyAny, game_state: &GameState) -> PyResult<GameState> {
+    let mut game_state = game_state.remove_gil_with_pads(py);
+
+    let num_cars: usize = extract(packet, "num_cars")?;
+    if num_cars != game_state.cars.len() {
+        return Err(PyValueError::new_err(format!(
+            "The packet has {num_cars} cars but the game state has {}",
+            game_state.cars.len()
+        )));
+    }
+
+    let num_boosts: usize = extract(packet, "num_boosts")?;
+    if num_boosts != game_state.pads.len() {
+        return Err(PyValueError::new_err(format!(
+            "The packet has {num_boosts} boost pads but the game state has {}",
+            game_state.pads.len()
+        )));
+    }
+
+    let game_cars = field(packet, "game_cars")?;
+    for (i, car) in game_state.cars.iter_mut().enumerate() {
+        let packet_car = game_cars.get_item(i)?;
+        let physics = field(packet_car, "physics")?;
+        let state = &mut car.state;
+
+        state.pos = vec3_from_packet(field(physics, "location")?)?;
+        state.rot_mat = rot_mat_from_packet(field(physics, "rotation")?)?;
+        state.vel = vec3_from_packet(field(physics, "velocity")?)?;
+        state.ang_vel = vec3_from_packet(field(physics, "angular_velocity")?)?;
+        state.is_demoed = extract(packet_car, "is_demolished")?;
+        state.is_on_ground = extract(packet_car, "has_wheel_contact")?;
+        state.is_supersonic = extract(packet_car, "is_super_sonic")?;
+        state.has_jumped = extract(packet_car, "jumped")?;
+        state.has_double_jumped = extract(packet_car, "double_jumped")?;
+        state.boost = extract(packet_car, "boost")?;
+    }
+
+    let game_boosts = field(packet, "game_boosts")?;
+    let order = rlbot_pad_order(game_state.pads.iter().map(|pad| (pad.position.x, pad.position.y)));
+    for (rlbot_index, i) in order.into_iter().enumerate() {
+        let packet_pad = game_boosts.get_item(rlbot_index)?;
+        let pad = &mut game_state.pads[i];
+
+        pad.state.is_active = extract(packet_pad, "is_active")?;
+        let timer: f32 = extract(packet_pad, "timer")?;
+        pad.state.cooldown = if pad.state.is_active { 0. } else { (pad_cooldown(pad.is_big) - timer).max(0.) };
+    }
+
+    let physics = field(field(packet, "game_ball")?, "physics")?;
+    game_state.ball.pos = vec3_from_packet(field(physics, "location")?)?;
+    game_state.ball.vel = vec3_from_packet(field(physics, "velocity")?)?;
+    game_state.ball.ang_vel = vec3_from_packet(field(physics, "angular_velocity")?)?;
+
+    game_state.tick_count = extract(field(packet, "game_info")?, "frame_num")?;
+
+    game_state.into_gil(py)
+}
+
+/// Returns a dict with the same shape and field names as RLBot's `FieldInfo` for the given game mode
+#[pyfunction]
+#[pyo3(signature = (gamemode=GameMode::Soccar))]
+pub fn get_field_info(py: Python<'_>, gamemode: GameMode) -> PyResult<&PyDict> {
+    let field = Field::from_gamemode(gamemode);
+
+    let boost_pads = PyList::empty(py);
+    for i in rlbot_pad_order(field.boost_pads.iter().map(|pad| (pad.pos.x, pad.pos.y))) {
+        let pad = &field.boost_pads[i];
+
+        let packet_pad = PyDict::new(py);
+        packet_pad.set_item("location", vec3_to_dict(py, pad.pos.into())?)?;
+        packet_pad.set_item("is_full_boost", pad.is_big)?;
+        boost_pads.append(packet_pad)?;
+    }
+
+    let goals = PyList::empty(py);
+    for goal in &field.goals {
+        let packet_goal = PyDict::new(py);
+        packet_goal.set_item("team_num", goal.team as u8)?;
+        packet_goal.set_item("location", vec3_to_dict(py, goal.center.into())?)?;
+        // the goals face into the field
+        packet_goal.set_item("direction", vec3_to_dict(py, CVec3::new(0., -goal.center.y.signum(), 0.))?)?;
+        packet_goal.set_item("width", goal.width)?;
+        packet_goal.set_item("height", goal.height)?;
+        goals.append(packet_goal)?;
+    }
+
+    let field_info = PyDict::new(py);
+    field_info.set_item("num_boosts", boost_pads.len())?;
+    field_info.set_item("boost_pads", boost_pads)?;
+    field_info.set_item("num_goals", goals.len())?;
+    field_info.set_item("goals", goals)?;
+    Ok(field_info)
+}
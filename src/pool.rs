@@ -0,0 +1,39 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static JOBS: OnceLock<Mutex<mpsc::Sender<Job>>> = OnceLock::new();
+
+/// Runs `job` on the first free worker thread, there's one worker for each CPU core
+///
+/// The workers are started on the first call and live until the process exits.
+pub fn spawn(job: impl FnOnce() + Send + 'static) {
+    let jobs = JOBS.get_or_init(start_workers).lock().unwrap();
+    jobs.send(Box::new(job)).expect("The workers never stop receiving jobs");
+}
+
+fn start_workers() -> Mutex<mpsc::Sender<Job>> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for i in 0..thread::available_parallelism().map_or(1, NonZeroUsize::get) {
+        let receiver = receiver.clone();
+        thread::Builder::new()
+            .name(format!("rocketsim-worker-{i}"))
+            .spawn(move || loop {
+                // the lock is released before the job runs, so the other workers can take the next one
+                let job = receiver.lock().unwrap().recv();
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => return,
+                }
+            })
+            .expect("Failed to start a worker thread");
+    }
+
+    Mutex::new(sender)
+}
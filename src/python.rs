@@ -1,14 +1,38 @@
-use pyo3::{exceptions::PyIndexError, prelude::*};
-use rocketsim_rs::{autocxx::prelude::*, cxx::UniquePtr, glam_ext::glam::Quat, sim as csim};
+use std::{
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, Weak},
+};
+
+use numpy::{PyArray1, PyArray2, PyArray3, PyReadonlyArray2};
+use pyo3::{
+    exceptions::{PyIndexError, PyMemoryError, PyValueError},
+    prelude::*,
+    pyclass::CompareOp,
+    types::{PyCFunction, PyDict, PyTuple},
+};
+use rocketsim_rs::{
+    autocxx::prelude::*,
+    cxx::UniquePtr,
+    glam_ext::glam::{Mat3A, Quat, Vec3 as GVec3, Vec3A},
+    math::{RotMat as CRotMat, Vec3 as CVec3},
+    sim as csim,
+};
 
 use crate::{
-    base::{repr_bool, FromGil, IntoGil, PyDefault, RemoveGil, RotMat, Vec3},
-    new_gil, new_gil_default,
+    base::{finish_hash, hash_f32, nested_value, repr_bool, richcmp_eq, RemoveGil, RotMat, RotMatRef, Vec3, Vec3Ref},
+    determinism::state_hash,
+    diff::{Differ, DEFAULT_TOLERANCE},
+    meshes::{check_initialized, raycast_soccar},
+    pool,
+    render::{check_image_size, render_topdown},
+    replay::{ArenaRecorder, EventTracker, ReplayEvent, ReplayEventKind},
     state::{BoostPad, CarInfo, GameState},
+    view::{BallView, CarView},
 };
 
 #[pyclass(module = "rocketsim.sim")]
-#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
 pub enum Team {
     #[default]
     Blue,
@@ -49,7 +73,7 @@ impl Team {
 }
 
 #[pyclass(module = "rocketsim.sim")]
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum GameMode {
     #[default]
     Soccar,
@@ -66,55 +90,46 @@ impl From<GameMode> for csim::GameMode {
     }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim.sim")]
-#[derive(Clone, Debug)]
+#[pyclass(set_all, subclass, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct BallHitInfo {
+    #[pyo3(get)]
     is_valid: bool,
-    relative_pos_on_ball: Py<Vec3>,
-    ball_pos: Py<Vec3>,
-    extra_hit_vel: Py<Vec3>,
+    relative_pos_on_ball: Vec3,
+    ball_pos: Vec3,
+    extra_hit_vel: Vec3,
+    #[pyo3(get)]
     tick_count_when_hit: u64,
+    #[pyo3(get)]
     tick_count_when_extra_impulse_applied: u64,
 }
 
-impl PyDefault for BallHitInfo {
-    #[inline]
-    fn py_default(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            is_valid: false,
-            relative_pos_on_ball: new_gil_default!(Vec3, py),
-            ball_pos: new_gil_default!(Vec3, py),
-            extra_hit_vel: new_gil_default!(Vec3, py),
-            tick_count_when_hit: 0,
-            tick_count_when_extra_impulse_applied: 0,
-        })
-    }
-}
+nested_value!(BallHitInfoRef, BallHitInfo);
 
-impl FromGil<csim::BallHitInfo> for BallHitInfo {
+impl From<csim::BallHitInfo> for BallHitInfo {
     #[inline]
-    fn from_gil(py: Python, hit: csim::BallHitInfo) -> PyResult<Self> {
-        Ok(Self {
+    fn from(hit: csim::BallHitInfo) -> Self {
+        Self {
             is_valid: hit.is_valid,
-            relative_pos_on_ball: new_gil!(Vec3, py, hit.relative_pos_on_ball),
-            ball_pos: new_gil!(Vec3, py, hit.ball_pos),
-            extra_hit_vel: new_gil!(Vec3, py, hit.extra_hit_vel),
+            relative_pos_on_ball: hit.relative_pos_on_ball.into(),
+            ball_pos: hit.ball_pos.into(),
+            extra_hit_vel: hit.extra_hit_vel.into(),
             tick_count_when_hit: hit.tick_count_when_hit,
             tick_count_when_extra_impulse_applied: hit.tick_count_when_extra_impulse_applied,
-        })
+        }
     }
 }
 
-impl RemoveGil<csim::BallHitInfo> for &BallHitInfo {
+impl From<BallHitInfo> for csim::BallHitInfo {
     #[inline]
-    fn remove_gil(self, py: Python) -> csim::BallHitInfo {
-        csim::BallHitInfo {
-            is_valid: self.is_valid,
-            relative_pos_on_ball: self.relative_pos_on_ball.clone().remove_gil(py),
-            ball_pos: self.ball_pos.clone().remove_gil(py),
-            extra_hit_vel: self.extra_hit_vel.clone().remove_gil(py),
-            tick_count_when_hit: self.tick_count_when_hit,
-            tick_count_when_extra_impulse_applied: self.tick_count_when_extra_impulse_applied,
+    fn from(hit: BallHitInfo) -> Self {
+        Self {
+            is_valid: hit.is_valid,
+            relative_pos_on_ball: hit.relative_pos_on_ball.into(),
+            ball_pos: hit.ball_pos.into(),
+            extra_hit_vel: hit.extra_hit_vel.into(),
+            tick_count_when_hit: hit.tick_count_when_hit,
+            tick_count_when_extra_impulse_applied: hit.tick_count_when_extra_impulse_applied,
         }
     }
 }
@@ -123,24 +138,34 @@ impl RemoveGil<csim::BallHitInfo> for &BallHitInfo {
 impl BallHitInfo {
     #[new]
     #[inline]
-    #[pyo3(signature = (is_valid=false, relative_pos_on_ball=None, ball_pos=None, extra_hit_vel=None, tick_count_when_hit=0, tick_count_when_extra_impulse_applied=0))]
-    fn __new__(
-        py: Python,
-        is_valid: bool,
-        relative_pos_on_ball: Option<Py<Vec3>>,
-        ball_pos: Option<Py<Vec3>>,
-        extra_hit_vel: Option<Py<Vec3>>,
-        tick_count_when_hit: u64,
-        tick_count_when_extra_impulse_applied: u64,
-    ) -> PyResult<Self> {
-        Ok(Self {
+    #[pyo3(signature = (is_valid=false, relative_pos_on_ball=Vec3::ZERO, ball_pos=Vec3::ZERO, extra_hit_vel=Vec3::ZERO, tick_count_when_hit=0, tick_count_when_extra_impulse_applied=0))]
+    fn __new__(is_valid: bool, relative_pos_on_ball: Vec3, ball_pos: Vec3, extra_hit_vel: Vec3, tick_count_when_hit: u64, tick_count_when_extra_impulse_applied: u64) -> Self {
+        Self {
             is_valid,
-            relative_pos_on_ball: relative_pos_on_ball.unwrap_or(new_gil_default!(Vec3, py)),
-            ball_pos: ball_pos.unwrap_or(new_gil_default!(Vec3, py)),
-            extra_hit_vel: extra_hit_vel.unwrap_or(new_gil_default!(Vec3, py)),
+            relative_pos_on_ball,
+            ball_pos,
+            extra_hit_vel,
             tick_count_when_hit,
             tick_count_when_extra_impulse_applied,
-        })
+        }
+    }
+
+    #[getter]
+    fn relative_pos_on_ball(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.relative_pos_on_ball;
+        Vec3Ref::wrap(py, slf.into_py(py), "relative_pos_on_ball", value)
+    }
+
+    #[getter]
+    fn ball_pos(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.ball_pos;
+        Vec3Ref::wrap(py, slf.into_py(py), "ball_pos", value)
+    }
+
+    #[getter]
+    fn extra_hit_vel(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.extra_hit_vel;
+        Vec3Ref::wrap(py, slf.into_py(py), "extra_hit_vel", value)
     }
 
     #[inline]
@@ -149,67 +174,52 @@ impl BallHitInfo {
     }
 
     #[inline]
-    fn __repr__(&self, py: Python) -> String {
+    fn __repr__(&self) -> String {
         format!(
             "BallHitInfo(is_valid={}, relative_pos_on_ball={}, ball_pos={}, extra_hit_vel={}, tick_count_when_hit={}, tick_count_when_extra_impulse_applied={})",
             repr_bool(self.is_valid),
-            self.relative_pos_on_ball.borrow(py).__repr__(),
-            self.ball_pos.borrow(py).__repr__(),
-            self.extra_hit_vel.borrow(py).__repr__(),
+            self.relative_pos_on_ball.__repr__(),
+            self.ball_pos.__repr__(),
+            self.extra_hit_vel.__repr__(),
             self.tick_count_when_hit,
             self.tick_count_when_extra_impulse_applied
         )
     }
-}
-
-#[pyclass(get_all, set_all, module = "rocketsim.sim")]
-#[derive(Clone, Debug)]
-pub struct Ball {
-    pos: Py<Vec3>,
-    vel: Py<Vec3>,
-    ang_vel: Py<Vec3>,
-}
 
-impl PyDefault for Ball {
     #[inline]
-    fn py_default(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            pos: new_gil_default!(Vec3, py),
-            vel: new_gil_default!(Vec3, py),
-            ang_vel: new_gil_default!(Vec3, py),
-        })
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
     }
 }
 
-impl FromGil<csim::BallState> for Ball {
-    #[inline]
-    fn from_gil(py: Python, ball: csim::BallState) -> PyResult<Self> {
-        Ok(Self {
-            pos: new_gil!(Vec3, py, ball.pos),
-            vel: new_gil!(Vec3, py, ball.vel),
-            ang_vel: new_gil!(Vec3, py, ball.ang_vel),
-        })
-    }
+#[pyclass(set_all, subclass, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ball {
+    pub pos: Vec3,
+    pub vel: Vec3,
+    pub ang_vel: Vec3,
 }
 
-impl RemoveGil<csim::BallState> for Ball {
+nested_value!(BallRef, Ball);
+
+impl From<csim::BallState> for Ball {
     #[inline]
-    fn remove_gil(self, py: Python) -> csim::BallState {
-        csim::BallState {
-            pos: self.pos.remove_gil(py),
-            vel: self.vel.remove_gil(py),
-            ang_vel: self.ang_vel.remove_gil(py),
+    fn from(ball: csim::BallState) -> Self {
+        Self {
+            pos: ball.pos.into(),
+            vel: ball.vel.into(),
+            ang_vel: ball.ang_vel.into(),
         }
     }
 }
 
-impl RemoveGil<csim::BallState> for &Ball {
+impl From<Ball> for csim::BallState {
     #[inline]
-    fn remove_gil(self, py: Python) -> csim::BallState {
-        csim::BallState {
-            pos: self.pos.clone().remove_gil(py),
-            vel: self.vel.clone().remove_gil(py),
-            ang_vel: self.ang_vel.clone().remove_gil(py),
+    fn from(ball: Ball) -> Self {
+        Self {
+            pos: ball.pos.into(),
+            vel: ball.vel.into(),
+            ang_vel: ball.ang_vel.into(),
         }
     }
 }
@@ -218,12 +228,27 @@ impl RemoveGil<csim::BallState> for &Ball {
 impl Ball {
     #[inline]
     #[new]
-    fn __new__(py: Python, pos: Option<Py<Vec3>>, vel: Option<Py<Vec3>>, ang_vel: Option<Py<Vec3>>) -> PyResult<Self> {
-        Ok(Self {
-            pos: pos.unwrap_or(new_gil_default!(Vec3, py)),
-            vel: vel.unwrap_or(new_gil_default!(Vec3, py)),
-            ang_vel: ang_vel.unwrap_or(new_gil_default!(Vec3, py)),
-        })
+    #[pyo3(signature = (pos=Vec3::ZERO, vel=Vec3::ZERO, ang_vel=Vec3::ZERO))]
+    fn __new__(pos: Vec3, vel: Vec3, ang_vel: Vec3) -> Self {
+        Self { pos, vel, ang_vel }
+    }
+
+    #[getter]
+    fn pos(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.pos;
+        Vec3Ref::wrap(py, slf.into_py(py), "pos", value)
+    }
+
+    #[getter]
+    fn vel(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.vel;
+        Vec3Ref::wrap(py, slf.into_py(py), "vel", value)
+    }
+
+    #[getter]
+    fn ang_vel(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.ang_vel;
+        Vec3Ref::wrap(py, slf.into_py(py), "ang_vel", value)
     }
 
     #[inline]
@@ -232,54 +257,55 @@ impl Ball {
     }
 
     #[inline]
-    pub fn __repr__(&self, py: Python) -> String {
-        format!(
-            "Ball(pos={}, vel={}, ang_vel={})",
-            self.pos.borrow(py).__repr__(),
-            self.vel.borrow(py).__repr__(),
-            self.ang_vel.borrow(py).__repr__()
-        )
+    pub fn __repr__(&self) -> String {
+        format!("Ball(pos={}, vel={}, ang_vel={})", self.pos.__repr__(), self.vel.__repr__(), self.ang_vel.__repr__())
+    }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    /// True if the position and velocities are all within the tolerance of the other ball's
+    #[pyo3(signature = (other, tolerance=DEFAULT_TOLERANCE))]
+    fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        let mut differ = Differ::new(tolerance);
+        differ.ball("ball", &(*self).into(), &(*other).into());
+        differ.diffs.is_empty()
     }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim.sim")]
-#[derive(Clone, Debug)]
+#[pyclass(set_all, subclass, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct WheelPairConfig {
+    #[pyo3(get)]
     wheel_radius: f32,
+    #[pyo3(get)]
     suspension_rest_length: f32,
-    connection_point_offset: Py<Vec3>,
+    connection_point_offset: Vec3,
 }
 
-impl PyDefault for WheelPairConfig {
-    #[inline]
-    fn py_default(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            wheel_radius: 0.0,
-            suspension_rest_length: 0.0,
-            connection_point_offset: new_gil!(Vec3, py, Vec3::ZERO),
-        })
-    }
-}
+nested_value!(WheelPairConfigRef, WheelPairConfig);
 
-impl RemoveGil<csim::WheelPairConfig> for &WheelPairConfig {
+impl From<WheelPairConfig> for csim::WheelPairConfig {
     #[inline]
-    fn remove_gil(self, py: Python) -> csim::WheelPairConfig {
-        csim::WheelPairConfig {
-            wheel_radius: self.wheel_radius,
-            suspension_rest_length: self.suspension_rest_length,
-            connection_point_offset: self.connection_point_offset.clone().remove_gil(py),
+    fn from(config: WheelPairConfig) -> Self {
+        Self {
+            wheel_radius: config.wheel_radius,
+            suspension_rest_length: config.suspension_rest_length,
+            connection_point_offset: config.connection_point_offset.into(),
         }
     }
 }
 
-impl FromGil<csim::WheelPairConfig> for WheelPairConfig {
+impl From<csim::WheelPairConfig> for WheelPairConfig {
     #[inline]
-    fn from_gil(py: Python, config: csim::WheelPairConfig) -> PyResult<Self> {
-        Ok(Self {
+    fn from(config: csim::WheelPairConfig) -> Self {
+        Self {
             wheel_radius: config.wheel_radius,
             suspension_rest_length: config.suspension_rest_length,
-            connection_point_offset: new_gil!(Vec3, py, config.connection_point_offset),
-        })
+            connection_point_offset: config.connection_point_offset.into(),
+        }
     }
 }
 
@@ -287,13 +313,19 @@ impl FromGil<csim::WheelPairConfig> for WheelPairConfig {
 impl WheelPairConfig {
     #[new]
     #[inline]
-    #[pyo3(signature = (wheel_radius=0., suspension_rest_length=0., connection_point_offset=None))]
-    fn __new__(py: Python, wheel_radius: f32, suspension_rest_length: f32, connection_point_offset: Option<Py<Vec3>>) -> PyResult<Self> {
-        Ok(Self {
+    #[pyo3(signature = (wheel_radius=0., suspension_rest_length=0., connection_point_offset=Vec3::ZERO))]
+    fn __new__(wheel_radius: f32, suspension_rest_length: f32, connection_point_offset: Vec3) -> Self {
+        Self {
             wheel_radius,
             suspension_rest_length,
-            connection_point_offset: connection_point_offset.unwrap_or(new_gil!(Vec3, py, Vec3::ZERO)),
-        })
+            connection_point_offset,
+        }
+    }
+
+    #[getter]
+    fn connection_point_offset(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.connection_point_offset;
+        Vec3Ref::wrap(py, slf.into_py(py), "connection_point_offset", value)
     }
 
     #[inline]
@@ -302,97 +334,334 @@ impl WheelPairConfig {
     }
 
     #[inline]
-    fn __repr__(&self, py: Python) -> String {
+    fn __repr__(&self) -> String {
         format!(
             "WheelPairConfig(wheel_radius={}, suspension_rest_length={}, connection_point_offset={})",
             self.wheel_radius,
             self.suspension_rest_length,
-            self.connection_point_offset.borrow(py).__repr__()
+            self.connection_point_offset.__repr__()
         )
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim.sim")]
-#[derive(Clone, Debug)]
-pub struct CarConfig {
-    hitbox_size: Py<Vec3>,
-    hitbox_pos_offset: Py<Vec3>,
-    front_wheels: Py<WheelPairConfig>,
-    back_wheels: Py<WheelPairConfig>,
-    dodge_deadzone: f32,
+#[pyclass(module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CarPreset {
+    Octane,
+    Dominus,
+    Plank,
+    Breakout,
+    Hybrid,
+    Merc,
 }
 
-impl PyDefault for CarConfig {
+impl CarPreset {
+    pub const ALL: [Self; 6] = [Self::Octane, Self::Dominus, Self::Plank, Self::Breakout, Self::Hybrid, Self::Merc];
+
     #[inline]
-    fn py_default(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            hitbox_size: new_gil_default!(Vec3, py),
-            hitbox_pos_offset: new_gil_default!(Vec3, py),
-            front_wheels: new_gil_default!(WheelPairConfig, py),
-            back_wheels: new_gil_default!(WheelPairConfig, py),
-            dodge_deadzone: 0.,
+    pub fn config(self) -> &'static csim::CarConfig {
+        match self {
+            Self::Octane => csim::CarConfig::octane(),
+            Self::Dominus => csim::CarConfig::dominus(),
+            Self::Plank => csim::CarConfig::plank(),
+            Self::Breakout => csim::CarConfig::breakout(),
+            Self::Hybrid => csim::CarConfig::hybrid(),
+            Self::Merc => csim::CarConfig::merc(),
+        }
+    }
+
+    #[inline]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Octane => "octane",
+            Self::Dominus => "dominus",
+            Self::Plank => "plank",
+            Self::Breakout => "breakout",
+            Self::Hybrid => "hybrid",
+            Self::Merc => "merc",
+        }
+    }
+
+    /// Looks up a preset by its name, ignoring case and surrounding whitespace
+    pub fn from_name(name: &str) -> PyResult<Self> {
+        let name = name.trim();
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown car preset \"{name}\", expected one of {}", Self::ALL.map(Self::name).join(", "))))
+    }
+
+    /// The preset that the config matches, allowing for float rounding in configs that were written to a file
+    pub fn find(config: &csim::CarConfig) -> Option<Self> {
+        const TOLERANCE: f32 = 1e-4;
+
+        let vec_eq = |a: CVec3, b: CVec3| (a.x - b.x).abs() <= TOLERANCE && (a.y - b.y).abs() <= TOLERANCE && (a.z - b.z).abs() <= TOLERANCE;
+        let wheels_eq = |a: &csim::WheelPairConfig, b: &csim::WheelPairConfig| {
+            (a.wheel_radius - b.wheel_radius).abs() <= TOLERANCE
+                && (a.suspension_rest_length - b.suspension_rest_length).abs() <= TOLERANCE
+                && vec_eq(a.connection_point_offset, b.connection_point_offset)
+        };
+
+        Self::ALL.into_iter().find(|preset| {
+            let preset_config = preset.config();
+            vec_eq(config.hitbox_size, preset_config.hitbox_size)
+                && vec_eq(config.hitbox_pos_offset, preset_config.hitbox_pos_offset)
+                && wheels_eq(&config.front_wheels, &preset_config.front_wheels)
+                && wheels_eq(&config.back_wheels, &preset_config.back_wheels)
+                && (config.dodge_deadzone - preset_config.dodge_deadzone).abs() <= TOLERANCE
         })
     }
 }
 
-impl FromGil<csim::CarConfig> for CarConfig {
+#[pymethods]
+impl CarPreset {
+    /// The lowercase name used by `CarConfig.from_name`
+    #[getter(name)]
     #[inline]
-    fn from_gil(py: Python, config: csim::CarConfig) -> PyResult<Self> {
-        Ok(Self {
-            hitbox_size: new_gil!(Vec3, py, config.hitbox_size),
-            hitbox_pos_offset: new_gil!(Vec3, py, config.hitbox_pos_offset),
-            front_wheels: new_gil!(WheelPairConfig, py, config.front_wheels),
-            back_wheels: new_gil!(WheelPairConfig, py, config.back_wheels),
-            dodge_deadzone: config.dodge_deadzone,
-        })
+    fn get_name(&self) -> &'static str {
+        self.name()
+    }
+
+    #[inline]
+    #[staticmethod]
+    #[pyo3(name = "from_name")]
+    fn py_from_name(name: &str) -> PyResult<Self> {
+        Self::from_name(name)
+    }
+
+    #[inline]
+    #[staticmethod]
+    fn all() -> Vec<Self> {
+        Self::ALL.to_vec()
+    }
+
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!("CarPreset.{self:?}")
     }
 }
 
-impl FromGil<&'static csim::CarConfig> for CarConfig {
+/// The longest suspension rest length that still keeps the car stable, the presets are all under 40
+const MAX_SUSPENSION_REST_LENGTH: f32 = 100.;
+
+/// Everything that would make the config simulate badly
+fn config_problems(config: &csim::CarConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    let is_finite = |vec: CVec3| vec.x.is_finite() && vec.y.is_finite() && vec.z.is_finite();
+
+    let size = config.hitbox_size;
+    if !is_finite(size) || size.x <= 0. || size.y <= 0. || size.z <= 0. {
+        problems.push(format!("hitbox_size must be positive in every dimension, got ({}, {}, {})", size.x, size.y, size.z));
+    }
+
+    if !is_finite(config.hitbox_pos_offset) {
+        problems.push("hitbox_pos_offset must be finite".to_string());
+    }
+
+    for (field, wheels) in [("front_wheels", &config.front_wheels), ("back_wheels", &config.back_wheels)] {
+        if !(wheels.wheel_radius.is_finite() && wheels.wheel_radius > 0.) {
+            problems.push(format!("{field}.wheel_radius must be above 0, got {}", wheels.wheel_radius));
+        }
+
+        if !(wheels.suspension_rest_length > 0. && wheels.suspension_rest_length <= MAX_SUSPENSION_REST_LENGTH) {
+            problems.push(format!(
+                "{field}.suspension_rest_length must be above 0 and at most {MAX_SUSPENSION_REST_LENGTH}, got {}",
+                wheels.suspension_rest_length
+            ));
+        }
+
+        if !is_finite(wheels.connection_point_offset) {
+            problems.push(format!("{field}.connection_point_offset must be finite"));
+        }
+    }
+
+    if !(0. ..=1.).contains(&config.dodge_deadzone) {
+        problems.push(format!("dodge_deadzone must be between 0 and 1, got {}", config.dodge_deadzone));
+    }
+
+    problems
+}
+
+pub fn validate_config(config: &csim::CarConfig) -> PyResult<()> {
+    let problems = config_problems(config);
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    Err(PyValueError::new_err(format!("Invalid CarConfig: {}", problems.join(", "))))
+}
+
+/// Every setter validates the whole config, so a `CarConfig` from Python is always valid
+#[pyclass(subclass, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CarConfig {
+    hitbox_size: Vec3,
+    hitbox_pos_offset: Vec3,
+    front_wheels: WheelPairConfig,
+    back_wheels: WheelPairConfig,
+    #[pyo3(get)]
+    dodge_deadzone: f32,
+}
+
+impl CarConfig {
+    /// Only applies `change` if the whole config is still valid afterwards
+    fn set_validated(&mut self, change: impl FnOnce(&mut Self)) -> PyResult<()> {
+        let mut config = *self;
+        change(&mut config);
+        validate_config(&config.into())?;
+        *self = config;
+        Ok(())
+    }
+}
+
+nested_value!(CarConfigRef, CarConfig);
+
+impl From<&csim::CarConfig> for CarConfig {
     #[inline]
-    fn from_gil(py: Python, config: &'static csim::CarConfig) -> PyResult<Self> {
-        Ok(Self {
-            hitbox_size: new_gil!(Vec3, py, config.hitbox_size),
-            hitbox_pos_offset: new_gil!(Vec3, py, config.hitbox_pos_offset),
-            front_wheels: new_gil!(WheelPairConfig, py, config.front_wheels),
-            back_wheels: new_gil!(WheelPairConfig, py, config.back_wheels),
+    fn from(config: &csim::CarConfig) -> Self {
+        Self {
+            hitbox_size: config.hitbox_size.into(),
+            hitbox_pos_offset: config.hitbox_pos_offset.into(),
+            front_wheels: config.front_wheels.into(),
+            back_wheels: config.back_wheels.into(),
             dodge_deadzone: config.dodge_deadzone,
-        })
+        }
     }
 }
 
-impl RemoveGil<csim::CarConfig> for &CarConfig {
+impl From<csim::CarConfig> for CarConfig {
     #[inline]
-    fn remove_gil(self, py: Python) -> csim::CarConfig {
-        csim::CarConfig {
-            hitbox_size: self.hitbox_size.clone().remove_gil(py),
-            hitbox_pos_offset: self.hitbox_pos_offset.clone().remove_gil(py),
-            front_wheels: self.front_wheels.borrow(py).remove_gil(py),
-            back_wheels: self.back_wheels.borrow(py).remove_gil(py),
-            dodge_deadzone: self.dodge_deadzone,
+    fn from(config: csim::CarConfig) -> Self {
+        Self::from(&config)
+    }
+}
+
+impl From<CarConfig> for csim::CarConfig {
+    #[inline]
+    fn from(config: CarConfig) -> Self {
+        Self {
+            hitbox_size: config.hitbox_size.into(),
+            hitbox_pos_offset: config.hitbox_pos_offset.into(),
+            front_wheels: config.front_wheels.into(),
+            back_wheels: config.back_wheels.into(),
+            dodge_deadzone: config.dodge_deadzone,
         }
     }
 }
 
 #[pymethods]
 impl CarConfig {
+    /// The fields that aren't given are the octane's, and the whole config is validated
     #[new]
-    #[inline]
     fn __new__(
-        py: Python,
-        hitbox_size: Option<Py<Vec3>>,
-        hitbox_pos_offset: Option<Py<Vec3>>,
-        front_wheels: Option<Py<WheelPairConfig>>,
-        back_wheels: Option<Py<WheelPairConfig>>,
+        hitbox_size: Option<Vec3>,
+        hitbox_pos_offset: Option<Vec3>,
+        front_wheels: Option<WheelPairConfig>,
+        back_wheels: Option<WheelPairConfig>,
         dodge_deadzone: Option<f32>,
     ) -> PyResult<Self> {
-        Ok(Self {
-            hitbox_size: hitbox_size.unwrap_or(new_gil_default!(Vec3, py)),
-            hitbox_pos_offset: hitbox_pos_offset.unwrap_or(new_gil_default!(Vec3, py)),
-            front_wheels: front_wheels.unwrap_or(new_gil_default!(WheelPairConfig, py)),
-            back_wheels: back_wheels.unwrap_or(new_gil_default!(WheelPairConfig, py)),
-            dodge_deadzone: dodge_deadzone.unwrap_or(0.5),
-        })
+        let octane = Self::from(csim::CarConfig::octane());
+        let config = Self {
+            hitbox_size: hitbox_size.unwrap_or(octane.hitbox_size),
+            hitbox_pos_offset: hitbox_pos_offset.unwrap_or(octane.hitbox_pos_offset),
+            front_wheels: front_wheels.unwrap_or(octane.front_wheels),
+            back_wheels: back_wheels.unwrap_or(octane.back_wheels),
+            dodge_deadzone: dodge_deadzone.unwrap_or(octane.dodge_deadzone),
+        };
+
+        validate_config(&config.into())?;
+        Ok(config)
+    }
+
+    #[getter]
+    fn hitbox_size(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.hitbox_size;
+        Vec3Ref::wrap(py, slf.into_py(py), "hitbox_size", value)
+    }
+
+    #[getter]
+    fn hitbox_pos_offset(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.hitbox_pos_offset;
+        Vec3Ref::wrap(py, slf.into_py(py), "hitbox_pos_offset", value)
+    }
+
+    #[getter]
+    fn front_wheels(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.front_wheels;
+        WheelPairConfigRef::wrap(py, slf.into_py(py), "front_wheels", value)
+    }
+
+    #[getter]
+    fn back_wheels(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.back_wheels;
+        WheelPairConfigRef::wrap(py, slf.into_py(py), "back_wheels", value)
+    }
+
+    #[setter]
+    fn set_hitbox_size(&mut self, hitbox_size: Vec3) -> PyResult<()> {
+        self.set_validated(|config| config.hitbox_size = hitbox_size)
+    }
+
+    #[setter]
+    fn set_hitbox_pos_offset(&mut self, hitbox_pos_offset: Vec3) -> PyResult<()> {
+        self.set_validated(|config| config.hitbox_pos_offset = hitbox_pos_offset)
+    }
+
+    #[setter]
+    fn set_front_wheels(&mut self, front_wheels: WheelPairConfig) -> PyResult<()> {
+        self.set_validated(|config| config.front_wheels = front_wheels)
+    }
+
+    #[setter]
+    fn set_back_wheels(&mut self, back_wheels: WheelPairConfig) -> PyResult<()> {
+        self.set_validated(|config| config.back_wheels = back_wheels)
+    }
+
+    #[setter]
+    fn set_dodge_deadzone(&mut self, dodge_deadzone: f32) -> PyResult<()> {
+        self.set_validated(|config| config.dodge_deadzone = dodge_deadzone)
+    }
+
+    /// Raises a ValueError listing everything that would make the config simulate badly
+    fn validate(&self) -> PyResult<()> {
+        validate_config(&(*self).into())
+    }
+
+    /// The 8 corners of the hitbox relative to the car's origin, with x forward, y right and z up
+    #[getter]
+    fn hitbox_corners(&self) -> Vec<Vec3> {
+        let size = self.hitbox_size;
+        let offset = self.hitbox_pos_offset;
+
+        [-1., 1.]
+            .into_iter()
+            .flat_map(|x| [-1., 1.].into_iter().flat_map(move |y| [-1., 1.].into_iter().map(move |z| (x, y, z))))
+            .map(|(x, y, z)| Vec3::new(offset.x + x * size.x / 2., offset.y + y * size.y / 2., offset.z + z * size.z / 2.))
+            .collect()
+    }
+
+    /// Distance between the front and back axles
+    #[getter]
+    #[inline]
+    fn wheelbase(&self) -> f32 {
+        self.front_wheels.connection_point_offset.x - self.back_wheels.connection_point_offset.x
+    }
+
+    /// Distance between the left and right wheels, averaged over the front and back axles
+    #[getter]
+    #[inline]
+    fn track_width(&self) -> f32 {
+        self.front_wheels.connection_point_offset.y.abs() + self.back_wheels.connection_point_offset.y.abs()
     }
 
     #[inline]
@@ -401,67 +670,108 @@ impl CarConfig {
     }
 
     #[inline]
-    pub fn __repr__(&self, py: Python) -> String {
+    pub fn __repr__(&self) -> String {
         format!(
             "CarConfig(hitbox_size={}, hitbox_pos_offset={}, front_wheels={}, back_wheels={}, dodge_deadzone={})",
-            self.hitbox_size.borrow(py).__repr__(),
-            self.hitbox_pos_offset.borrow(py).__repr__(),
-            self.front_wheels.borrow(py).__repr__(py),
-            self.back_wheels.borrow(py).__repr__(py),
+            self.hitbox_size.__repr__(),
+            self.hitbox_pos_offset.__repr__(),
+            self.front_wheels.__repr__(),
+            self.back_wheels.__repr__(),
             self.dodge_deadzone
         )
     }
 
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    #[inline]
+    #[staticmethod]
+    fn octane() -> Self {
+        csim::CarConfig::octane().into()
+    }
+
     #[inline]
     #[staticmethod]
-    fn octane(py: Python) -> PyResult<Self> {
-        csim::CarConfig::octane().into_gil(py)
+    fn dominus() -> Self {
+        csim::CarConfig::dominus().into()
     }
 
     #[inline]
     #[staticmethod]
-    fn dominus(py: Python) -> PyResult<Self> {
-        csim::CarConfig::dominus().into_gil(py)
+    fn plank() -> Self {
+        csim::CarConfig::plank().into()
     }
 
     #[inline]
     #[staticmethod]
-    fn plank(py: Python) -> PyResult<Self> {
-        csim::CarConfig::plank().into_gil(py)
+    fn breakout() -> Self {
+        csim::CarConfig::breakout().into()
     }
 
     #[inline]
     #[staticmethod]
-    fn breakout(py: Python) -> PyResult<Self> {
-        csim::CarConfig::breakout().into_gil(py)
+    fn hybrid() -> Self {
+        csim::CarConfig::hybrid().into()
     }
 
     #[inline]
     #[staticmethod]
-    fn hybrid(py: Python) -> PyResult<Self> {
-        csim::CarConfig::hybrid().into_gil(py)
+    fn merc() -> Self {
+        csim::CarConfig::merc().into()
     }
 
     #[inline]
     #[staticmethod]
-    fn merc(py: Python) -> PyResult<Self> {
-        csim::CarConfig::merc().into_gil(py)
+    fn from_preset(preset: CarPreset) -> Self {
+        preset.config().into()
+    }
+
+    /// Looks up a preset by name, like "octane" or "Dominus"
+    #[inline]
+    #[staticmethod]
+    fn from_name(name: &str) -> PyResult<Self> {
+        Ok(CarPreset::from_name(name)?.config().into())
+    }
+
+    /// Every preset and its config, in the order of `CarPreset.all()`
+    #[staticmethod]
+    fn all_presets() -> Vec<(CarPreset, Self)> {
+        CarPreset::ALL.into_iter().map(|preset| (preset, preset.config().into())).collect()
+    }
+
+    /// The preset that this config matches, or None if it's a custom config
+    #[getter]
+    #[inline]
+    fn preset(&self) -> Option<CarPreset> {
+        CarPreset::find(&(*self).into())
     }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim.sim")]
+#[pyclass(set_all, subclass, module = "rocketsim.sim")]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct CarControls {
+    #[pyo3(get)]
     throttle: f32,
+    #[pyo3(get)]
     steer: f32,
+    #[pyo3(get)]
     pitch: f32,
+    #[pyo3(get)]
     yaw: f32,
+    #[pyo3(get)]
     roll: f32,
+    #[pyo3(get)]
     jump: bool,
+    #[pyo3(get)]
     boost: bool,
+    #[pyo3(get)]
     handbrake: bool,
 }
 
+nested_value!(CarControlsRef, CarControls);
+
 impl From<csim::CarControls> for CarControls {
     #[inline]
     fn from(controls: csim::CarControls) -> Self {
@@ -548,92 +858,83 @@ impl CarControls {
             repr_bool(self.handbrake)
         )
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim.sim")]
-#[derive(Clone, Debug)]
+#[pyclass(set_all, subclass, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Car {
-    pos: Py<Vec3>,
-    rot_mat: Py<RotMat>,
-    vel: Py<Vec3>,
-    ang_vel: Py<Vec3>,
-    is_on_ground: bool,
-    has_jumped: bool,
-    has_double_jumped: bool,
-    has_flipped: bool,
-    last_rel_dodge_torque: Py<Vec3>,
-    jump_time: f32,
-    flip_time: f32,
-    is_jumping: bool,
-    air_time_since_jump: f32,
-    boost: f32,
-    time_spent_boosting: f32,
-    is_supersonic: bool,
-    supersonic_time: f32,
-    handbrake_val: f32,
-    is_auto_flipping: bool,
-    auto_flip_timer: f32,
-    auto_flip_torque_scale: f32,
-    has_contact: bool,
-    contact_normal: Py<Vec3>,
-    other_car_id: u32,
-    cooldown_timer: f32,
-    is_demoed: bool,
-    demo_respawn_timer: f32,
-    ball_hit_info: Py<BallHitInfo>,
-    last_controls: Py<CarControls>,
+    pub pos: Vec3,
+    pub rot_mat: RotMat,
+    pub vel: Vec3,
+    pub ang_vel: Vec3,
+    #[pyo3(get)]
+    pub is_on_ground: bool,
+    #[pyo3(get)]
+    pub has_jumped: bool,
+    #[pyo3(get)]
+    pub has_double_jumped: bool,
+    #[pyo3(get)]
+    pub has_flipped: bool,
+    pub last_rel_dodge_torque: Vec3,
+    #[pyo3(get)]
+    pub jump_time: f32,
+    #[pyo3(get)]
+    pub flip_time: f32,
+    #[pyo3(get)]
+    pub is_jumping: bool,
+    #[pyo3(get)]
+    pub air_time_since_jump: f32,
+    #[pyo3(get)]
+    pub boost: f32,
+    #[pyo3(get)]
+    pub time_spent_boosting: f32,
+    #[pyo3(get)]
+    pub is_supersonic: bool,
+    #[pyo3(get)]
+    pub supersonic_time: f32,
+    #[pyo3(get)]
+    pub handbrake_val: f32,
+    #[pyo3(get)]
+    pub is_auto_flipping: bool,
+    #[pyo3(get)]
+    pub auto_flip_timer: f32,
+    #[pyo3(get)]
+    pub auto_flip_torque_scale: f32,
+    #[pyo3(get)]
+    pub has_contact: bool,
+    pub contact_normal: Vec3,
+    #[pyo3(get)]
+    pub other_car_id: u32,
+    #[pyo3(get)]
+    pub cooldown_timer: f32,
+    #[pyo3(get)]
+    pub is_demoed: bool,
+    #[pyo3(get)]
+    pub demo_respawn_timer: f32,
+    pub ball_hit_info: BallHitInfo,
+    pub last_controls: CarControls,
 }
 
-impl PyDefault for Car {
-    #[inline]
-    fn py_default(py: Python) -> PyResult<Self> {
-        Ok(Self {
-            pos: new_gil_default!(Vec3, py),
-            rot_mat: new_gil_default!(RotMat, py),
-            vel: new_gil_default!(Vec3, py),
-            ang_vel: new_gil_default!(Vec3, py),
-            is_on_ground: false,
-            has_jumped: false,
-            has_double_jumped: false,
-            has_flipped: false,
-            last_rel_dodge_torque: new_gil_default!(Vec3, py),
-            jump_time: 0.0,
-            flip_time: 0.0,
-            is_jumping: false,
-            air_time_since_jump: 0.0,
-            boost: 0.0,
-            time_spent_boosting: 0.0,
-            is_supersonic: false,
-            supersonic_time: 0.0,
-            handbrake_val: 0.0,
-            is_auto_flipping: false,
-            auto_flip_timer: 0.0,
-            auto_flip_torque_scale: 0.0,
-            has_contact: false,
-            contact_normal: new_gil_default!(Vec3, py),
-            other_car_id: 0,
-            cooldown_timer: 0.0,
-            is_demoed: false,
-            demo_respawn_timer: 0.0,
-            ball_hit_info: new_gil_default!(BallHitInfo, py),
-            last_controls: new_gil_default!(CarControls, py),
-        })
-    }
-}
+nested_value!(CarRef, Car);
 
-impl FromGil<csim::CarState> for Car {
+impl From<csim::CarState> for Car {
     #[inline]
-    fn from_gil(py: Python, car: csim::CarState) -> PyResult<Self> {
-        Ok(Self {
-            pos: new_gil!(Vec3, py, car.pos),
-            rot_mat: new_gil!(RotMat, py, car.rot_mat),
-            vel: new_gil!(Vec3, py, car.vel),
-            ang_vel: new_gil!(Vec3, py, car.ang_vel),
+    fn from(car: csim::CarState) -> Self {
+        Self {
+            pos: car.pos.into(),
+            rot_mat: car.rot_mat.into(),
+            vel: car.vel.into(),
+            ang_vel: car.ang_vel.into(),
             is_on_ground: car.is_on_ground,
             has_jumped: car.has_jumped,
             has_double_jumped: car.has_double_jumped,
             has_flipped: car.has_flipped,
-            last_rel_dodge_torque: new_gil!(Vec3, py, car.last_rel_dodge_torque),
+            last_rel_dodge_torque: car.last_rel_dodge_torque.into(),
             jump_time: car.jump_time,
             flip_time: car.flip_time,
             is_jumping: car.is_jumping,
@@ -647,87 +948,50 @@ impl FromGil<csim::CarState> for Car {
             auto_flip_timer: car.auto_flip_timer,
             auto_flip_torque_scale: car.auto_flip_torque_scale,
             has_contact: car.has_contact,
-            contact_normal: new_gil!(Vec3, py, car.contact_normal),
+            contact_normal: car.contact_normal.into(),
             other_car_id: car.other_car_id,
             cooldown_timer: car.cooldown_timer,
             is_demoed: car.is_demoed,
             demo_respawn_timer: car.demo_respawn_timer,
-            ball_hit_info: new_gil!(BallHitInfo, py, car.ball_hit_info),
-            last_controls: new_gil!(CarControls, py, car.last_controls),
-        })
-    }
-}
-
-impl RemoveGil<csim::CarState> for &Car {
-    #[inline]
-    fn remove_gil(self, py: Python) -> csim::CarState {
-        csim::CarState {
-            pos: self.pos.clone().remove_gil(py),
-            rot_mat: self.rot_mat.borrow(py).clone().remove_gil(py),
-            vel: self.vel.clone().remove_gil(py),
-            ang_vel: self.ang_vel.clone().remove_gil(py),
-            is_on_ground: self.is_on_ground,
-            has_jumped: self.has_jumped,
-            has_double_jumped: self.has_double_jumped,
-            has_flipped: self.has_flipped,
-            last_rel_dodge_torque: self.last_rel_dodge_torque.clone().remove_gil(py),
-            jump_time: self.jump_time,
-            flip_time: self.flip_time,
-            is_jumping: self.is_jumping,
-            air_time_since_jump: self.air_time_since_jump,
-            boost: self.boost,
-            time_spent_boosting: self.time_spent_boosting,
-            is_supersonic: self.is_supersonic,
-            supersonic_time: self.supersonic_time,
-            handbrake_val: self.handbrake_val,
-            is_auto_flipping: self.is_auto_flipping,
-            auto_flip_timer: self.auto_flip_timer,
-            auto_flip_torque_scale: self.auto_flip_torque_scale,
-            has_contact: self.has_contact,
-            contact_normal: self.contact_normal.clone().remove_gil(py),
-            other_car_id: self.other_car_id,
-            cooldown_timer: self.cooldown_timer,
-            is_demoed: self.is_demoed,
-            demo_respawn_timer: self.demo_respawn_timer,
-            ball_hit_info: self.ball_hit_info.borrow(py).remove_gil(py),
-            last_controls: self.last_controls.clone().remove_gil(py),
+            ball_hit_info: car.ball_hit_info.into(),
+            last_controls: car.last_controls.into(),
         }
     }
 }
 
-impl RemoveGil<csim::CarState> for Car {
-    #[inline]
-    fn remove_gil(self, py: Python) -> csim::CarState {
-        csim::CarState {
-            pos: self.pos.remove_gil(py),
-            rot_mat: self.rot_mat.borrow(py).clone().remove_gil(py),
-            vel: self.vel.remove_gil(py),
-            ang_vel: self.ang_vel.remove_gil(py),
-            is_on_ground: self.is_on_ground,
-            has_jumped: self.has_jumped,
-            has_double_jumped: self.has_double_jumped,
-            has_flipped: self.has_flipped,
-            last_rel_dodge_torque: self.last_rel_dodge_torque.remove_gil(py),
-            jump_time: self.jump_time,
-            flip_time: self.flip_time,
-            is_jumping: self.is_jumping,
-            air_time_since_jump: self.air_time_since_jump,
-            boost: self.boost,
-            time_spent_boosting: self.time_spent_boosting,
-            is_supersonic: self.is_supersonic,
-            supersonic_time: self.supersonic_time,
-            handbrake_val: self.handbrake_val,
-            is_auto_flipping: self.is_auto_flipping,
-            auto_flip_timer: self.auto_flip_timer,
-            auto_flip_torque_scale: self.auto_flip_torque_scale,
-            has_contact: self.has_contact,
-            contact_normal: self.contact_normal.remove_gil(py),
-            other_car_id: self.other_car_id,
-            cooldown_timer: self.cooldown_timer,
-            is_demoed: self.is_demoed,
-            demo_respawn_timer: self.demo_respawn_timer,
-            ball_hit_info: self.ball_hit_info.borrow(py).remove_gil(py),
-            last_controls: self.last_controls.remove_gil(py),
+impl From<Car> for csim::CarState {
+    #[inline]
+    fn from(car: Car) -> Self {
+        Self {
+            pos: car.pos.into(),
+            rot_mat: car.rot_mat.into(),
+            vel: car.vel.into(),
+            ang_vel: car.ang_vel.into(),
+            is_on_ground: car.is_on_ground,
+            has_jumped: car.has_jumped,
+            has_double_jumped: car.has_double_jumped,
+            has_flipped: car.has_flipped,
+            last_rel_dodge_torque: car.last_rel_dodge_torque.into(),
+            jump_time: car.jump_time,
+            flip_time: car.flip_time,
+            is_jumping: car.is_jumping,
+            air_time_since_jump: car.air_time_since_jump,
+            boost: car.boost,
+            time_spent_boosting: car.time_spent_boosting,
+            is_supersonic: car.is_supersonic,
+            supersonic_time: car.supersonic_time,
+            handbrake_val: car.handbrake_val,
+            is_auto_flipping: car.is_auto_flipping,
+            auto_flip_timer: car.auto_flip_timer,
+            auto_flip_torque_scale: car.auto_flip_torque_scale,
+            has_contact: car.has_contact,
+            contact_normal: car.contact_normal.into(),
+            other_car_id: car.other_car_id,
+            cooldown_timer: car.cooldown_timer,
+            is_demoed: car.is_demoed,
+            demo_respawn_timer: car.demo_respawn_timer,
+            ball_hit_info: car.ball_hit_info.into(),
+            last_controls: car.last_controls.into(),
         }
     }
 }
@@ -738,15 +1002,15 @@ impl Car {
     #[inline]
     #[allow(clippy::too_many_arguments)]
     #[pyo3(signature = (
-        pos=None,
-        rot_mat=None,
-        vel=None,
-        ang_vel=None,
+        pos=Vec3::ZERO,
+        rot_mat=RotMat::ZERO,
+        vel=Vec3::ZERO,
+        ang_vel=Vec3::ZERO,
         is_on_ground=false,
         has_jumped=false,
         has_double_jumped=false,
         has_flipped=false,
-        last_rel_dodge_torque=None,
+        last_rel_dodge_torque=Vec3::ZERO,
         jump_time=0.,
         flip_time=0.,
         is_jumping=false,
@@ -760,25 +1024,24 @@ impl Car {
         auto_flip_timer=0.,
         auto_flip_torque_scale=0.,
         has_contact=false,
-        contact_normal=None,
+        contact_normal=Vec3::ZERO,
         other_car_id=0,
         cooldown_timer=0.,
         is_demoed=false,
         demo_respawn_timer=0.,
-        ball_hit_info=None,
-        last_controls=None
+        ball_hit_info=BallHitInfo::default(),
+        last_controls=CarControls::default()
     ))]
     fn __new__(
-        py: Python,
-        pos: Option<Py<Vec3>>,
-        rot_mat: Option<Py<RotMat>>,
-        vel: Option<Py<Vec3>>,
-        ang_vel: Option<Py<Vec3>>,
+        pos: Vec3,
+        rot_mat: RotMat,
+        vel: Vec3,
+        ang_vel: Vec3,
         is_on_ground: bool,
         has_jumped: bool,
         has_double_jumped: bool,
         has_flipped: bool,
-        last_rel_dodge_torque: Option<Py<Vec3>>,
+        last_rel_dodge_torque: Vec3,
         jump_time: f32,
         flip_time: f32,
         is_jumping: bool,
@@ -792,24 +1055,24 @@ impl Car {
         auto_flip_timer: f32,
         auto_flip_torque_scale: f32,
         has_contact: bool,
-        contact_normal: Option<Py<Vec3>>,
+        contact_normal: Vec3,
         other_car_id: u32,
         cooldown_timer: f32,
         is_demoed: bool,
         demo_respawn_timer: f32,
-        ball_hit_info: Option<Py<BallHitInfo>>,
-        last_controls: Option<Py<CarControls>>,
-    ) -> PyResult<Self> {
-        Ok(Self {
-            pos: pos.unwrap_or(new_gil_default!(Vec3, py)),
-            rot_mat: rot_mat.unwrap_or(new_gil_default!(RotMat, py)),
-            vel: vel.unwrap_or(new_gil_default!(Vec3, py)),
-            ang_vel: ang_vel.unwrap_or(new_gil_default!(Vec3, py)),
+        ball_hit_info: BallHitInfo,
+        last_controls: CarControls,
+    ) -> Self {
+        Self {
+            pos,
+            rot_mat,
+            vel,
+            ang_vel,
             is_on_ground,
             has_jumped,
             has_double_jumped,
             has_flipped,
-            last_rel_dodge_torque: last_rel_dodge_torque.unwrap_or(new_gil_default!(Vec3, py)),
+            last_rel_dodge_torque,
             jump_time,
             flip_time,
             is_jumping,
@@ -823,14 +1086,62 @@ impl Car {
             auto_flip_timer,
             auto_flip_torque_scale,
             has_contact,
-            contact_normal: contact_normal.unwrap_or(new_gil_default!(Vec3, py)),
+            contact_normal,
             other_car_id,
             cooldown_timer,
             is_demoed,
             demo_respawn_timer,
-            ball_hit_info: ball_hit_info.unwrap_or(new_gil_default!(BallHitInfo, py)),
-            last_controls: last_controls.unwrap_or(new_gil_default!(CarControls, py)),
-        })
+            ball_hit_info,
+            last_controls,
+        }
+    }
+
+    #[getter]
+    fn pos(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.pos;
+        Vec3Ref::wrap(py, slf.into_py(py), "pos", value)
+    }
+
+    #[getter]
+    fn rot_mat(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.rot_mat;
+        RotMatRef::wrap(py, slf.into_py(py), "rot_mat", value)
+    }
+
+    #[getter]
+    fn vel(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.vel;
+        Vec3Ref::wrap(py, slf.into_py(py), "vel", value)
+    }
+
+    #[getter]
+    fn ang_vel(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.ang_vel;
+        Vec3Ref::wrap(py, slf.into_py(py), "ang_vel", value)
+    }
+
+    #[getter]
+    fn last_rel_dodge_torque(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.last_rel_dodge_torque;
+        Vec3Ref::wrap(py, slf.into_py(py), "last_rel_dodge_torque", value)
+    }
+
+    #[getter]
+    fn contact_normal(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.contact_normal;
+        Vec3Ref::wrap(py, slf.into_py(py), "contact_normal", value)
+    }
+
+    #[getter]
+    fn ball_hit_info(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.ball_hit_info;
+        BallHitInfoRef::wrap(py, slf.into_py(py), "ball_hit_info", value)
+    }
+
+    #[getter]
+    fn last_controls(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.last_controls;
+        CarControlsRef::wrap(py, slf.into_py(py), "last_controls", value)
     }
 
     #[inline]
@@ -839,18 +1150,18 @@ impl Car {
     }
 
     #[inline]
-    pub fn __repr__(&self, py: Python) -> String {
+    pub fn __repr__(&self) -> String {
         format!(
             "Car(pos={}, rot_mat={}, vel={}, ang_vel={}, is_on_ground={}, has_jumped={}, has_double_jumped={}, has_flipped={}, last_rel_dodge_torque={}, jump_time={}, flip_time={}, is_jumping={}, air_time_since_jump={}, boost={}, time_spent_boosting={}, is_supersonic={}, supersonic_time={}, handbrake_val={}, is_auto_flipping={}, auto_flip_timer={}, auto_flip_torque_scale={}, has_contact={}, contact_normal={}, other_car_id={}, cooldown_timer={}, is_demoed={}, demo_respawn_timer={}, ball_hit_info={}, last_controls={})",
-            self.pos.borrow(py).__repr__(),
-            self.rot_mat.borrow(py).__repr__(py),
-            self.vel.borrow(py).__repr__(),
-            self.ang_vel.borrow(py).__repr__(),
+            self.pos.__repr__(),
+            self.rot_mat.__repr__(),
+            self.vel.__repr__(),
+            self.ang_vel.__repr__(),
             repr_bool(self.is_on_ground),
             repr_bool(self.has_jumped),
             repr_bool(self.has_double_jumped),
             repr_bool(self.has_flipped),
-            self.last_rel_dodge_torque.borrow(py).__repr__(),
+            self.last_rel_dodge_torque.__repr__(),
             self.jump_time,
             self.flip_time,
             repr_bool(self.is_jumping),
@@ -864,50 +1175,91 @@ impl Car {
             self.auto_flip_timer,
             self.auto_flip_torque_scale,
             repr_bool(self.has_contact),
-            self.contact_normal.borrow(py).__repr__(),
+            self.contact_normal.__repr__(),
             self.other_car_id,
             self.cooldown_timer,
             repr_bool(self.is_demoed),
             self.demo_respawn_timer,
-            self.ball_hit_info.borrow(py).__repr__(py),
-            self.last_controls.borrow(py).__repr__()
+            self.ball_hit_info.__repr__(),
+            self.last_controls.__repr__()
         )
     }
 
     #[inline]
-    fn get_contacting_car(&self, py: Python, arena: &mut Arena) -> PyResult<Option<Self>> {
-        Ok(if let Some(car) = self.remove_gil(py).get_contacting_car(arena.0.pin_mut()) {
-            Some(car.into_gil(py)?)
-        } else {
-            None
-        })
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    #[inline]
+    fn get_contacting_car(&self, arena: &mut Arena) -> Option<Self> {
+        csim::CarState::from(*self).get_contacting_car(arena.0.pin_mut()).map(Self::from)
+    }
+
+    /// The 8 world space corners of the car's hitbox, in the same order as `CarConfig.hitbox_corners`
+    fn hitbox_corners(&self, config: &CarConfig) -> Vec<Vec3> {
+        let (_, _, corners) = hitbox_obb(GVec3::from(self.pos).into(), self.rot_mat.into(), &(*config).into());
+        corners.into_iter().map(Vec3::from).collect()
+    }
+
+    /// True if every field is within the tolerance of the other car's, with bools compared as 0 or 1
+    #[pyo3(signature = (other, tolerance=DEFAULT_TOLERANCE))]
+    fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        let mut differ = Differ::new(tolerance);
+        differ.car("car", &(*self).into(), &(*other).into());
+        differ.diffs.is_empty()
     }
 }
 
-#[pyclass(get_all, frozen, module = "rocketsim.sim")]
+#[pyclass(frozen, module = "rocketsim.sim")]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BoostPadStatic {
-    pos: Vec3,
-    is_big: bool,
+    pub pos: Vec3,
+    #[pyo3(get)]
+    pub is_big: bool,
 }
 
 #[pymethods]
 impl BoostPadStatic {
+    #[getter]
+    fn pos(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.pos;
+        Vec3Ref::wrap(py, slf.into_py(py), "pos", value)
+    }
+
     #[inline]
     fn __str__(&self) -> String {
         format!("{self:?}")
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    #[inline]
+    fn __hash__(&self) -> u64 {
+        finish_hash(|state| {
+            self.pos.hash_components(state);
+            self.is_big.hash(state);
+        })
+    }
 }
 
-#[pyclass(set_all, get_all, module = "rocketsim.sim")]
+#[pyclass(set_all, subclass, module = "rocketsim.sim")]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct BoostPadState {
+    #[pyo3(get)]
     pub is_active: bool,
+    #[pyo3(get)]
     pub cooldown: f32,
+    #[pyo3(get)]
     pub cur_locked_car_id: u32,
+    #[pyo3(get)]
     pub prev_locked_car_id: u32,
 }
 
+nested_value!(BoostPadStateRef, BoostPadState);
+
 impl From<csim::BoostPadState> for BoostPadState {
     #[inline]
     fn from(boost_pad_state: csim::BoostPadState) -> Self {
@@ -973,11 +1325,347 @@ impl BoostPadState {
             self.prev_locked_car_id
         )
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+}
+
+/// The center, half extents and 8 world space corners of a car's hitbox
+///
+/// The corners are in the same order as `CarConfig.hitbox_corners`
+pub fn hitbox_obb(pos: Vec3A, rot_mat: CRotMat, config: &csim::CarConfig) -> (GVec3, GVec3, [GVec3; 8]) {
+    let rot_mat = Mat3A::from(rot_mat);
+    let half_extents = Vec3A::from(config.hitbox_size) / 2.;
+    let center = pos + rot_mat.mul_vec3a(config.hitbox_pos_offset.into());
+
+    let mut corners = [GVec3::ZERO; 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let sign = Vec3A::new(if i & 4 == 0 { -1. } else { 1. }, if i & 2 == 0 { -1. } else { 1. }, if i & 1 == 0 { -1. } else { 1. });
+        *corner = (center + rot_mat.mul_vec3a(sign * half_extents)).into();
+    }
+
+    (center.into(), half_extents.into(), corners)
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim.sim")]
+#[derive(Clone, Debug)]
+pub struct CarObb {
+    /// The center of the hitbox in world space
+    center: Vec3,
+    /// Half of the hitbox size along each of the car's local axes
+    half_extents: Vec3,
+    /// The car's orientation, which the half extents are along
+    rot_mat: RotMat,
+    /// The 8 world space corners, in the same order as `CarConfig.hitbox_corners`
+    corners: Vec<Vec3>,
+}
+
+#[pymethods]
+impl CarObb {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!(
+            "CarObb(center={}, half_extents={}, rot_mat={})",
+            self.center.__repr__(),
+            self.half_extents.__repr__(),
+            self.rot_mat.__repr__()
+        )
+    }
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Where the ray hit the arena
+    pos: Vec3,
+    /// The surface normal at the hit, facing back towards the ray
+    normal: Vec3,
+    /// The distance from the ray's origin to the hit
+    distance: f32,
+}
+
+#[pymethods]
+impl RayHit {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!("RayHit(pos={}, normal={}, distance={})", self.pos.__repr__(), self.normal.__repr__(), self.distance)
+    }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
+
+    #[inline]
+    fn __hash__(&self) -> u64 {
+        finish_hash(|state| {
+            self.pos.hash_components(state);
+            self.normal.hash_components(state);
+            hash_f32(self.distance, state);
+        })
+    }
+}
+
+/// A field that `Arena.step_record` samples for the ball and each car
+#[derive(Clone, Copy, Debug)]
+enum RecordField {
+    Pos,
+    Vel,
+    AngVel,
+    RotMat,
+}
+
+impl RecordField {
+    fn from_name(name: &str) -> PyResult<Self> {
+        Ok(match name {
+            "pos" => Self::Pos,
+            "vel" => Self::Vel,
+            "ang_vel" => Self::AngVel,
+            "rot_mat" => Self::RotMat,
+            _ => return Err(PyValueError::new_err(format!("Unknown field {name:?}, expected pos, vel, ang_vel or rot_mat"))),
+        })
+    }
+
+    #[inline]
+    const fn num_values(self) -> usize {
+        match self {
+            Self::RotMat => 9,
+            _ => 3,
+        }
+    }
+
+    /// Adds the values of this field to `sample`, with `rot_mat` as its forward, right and up axes
+    fn extend(self, sample: &mut Vec<f32>, pos: CVec3, vel: CVec3, ang_vel: CVec3, rot_mat: CRotMat) {
+        let vecs = match self {
+            Self::Pos => vec![pos],
+            Self::Vel => vec![vel],
+            Self::AngVel => vec![ang_vel],
+            Self::RotMat => vec![rot_mat.forward, rot_mat.right, rot_mat.up],
+        };
+
+        sample.extend(vecs.into_iter().flat_map(|vec| [vec.x, vec.y, vec.z]));
+    }
+}
+
+/// Finishes a future from `Arena.step_async` on the event loop's thread, unless it was cancelled while the arena stepped
+fn complete_future(args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+    let (future, value, is_error): (&PyAny, &PyAny, bool) = args.extract()?;
+    if !future.call_method0("done")?.is_true()? {
+        future.call_method1(if is_error { "set_exception" } else { "set_result" }, (value,))?;
+    }
+
+    Ok(())
+}
+
+/// An arena that `Arena.step_async` borrowed on the calling thread, so it's busy while the step waits for a worker
+struct QueuedBorrow {
+    /// Declared first so it's released before `_owner`, which keeps the borrowed cell alive
+    borrow: PyRefMut<'static, Arena>,
+    _owner: Py<Arena>,
+}
+
+impl QueuedBorrow {
+    fn new(cell: &PyCell<Arena>) -> PyResult<Self> {
+        let owner: Py<Arena> = cell.into();
+        // SAFETY: `owner` holds a reference to the cell for as long as the borrow exists
+        let cell: &'static PyCell<Arena> = unsafe { &*(cell as *const PyCell<Arena>) };
+        Ok(Self {
+            borrow: cell.try_borrow_mut()?,
+            _owner: owner,
+        })
+    }
+}
+
+impl Deref for QueuedBorrow {
+    type Target = Arena;
+
+    #[inline]
+    fn deref(&self) -> &Arena {
+        &self.borrow
+    }
+}
+
+impl DerefMut for QueuedBorrow {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Arena {
+        &mut self.borrow
+    }
 }
 
-#[pyclass(unsendable, module = "rocketsim.sim")]
-#[repr(transparent)]
-pub struct Arena(UniquePtr<csim::Arena>);
+// SAFETY: the borrow flag isn't atomic, but the worker only uses and releases the borrow while it holds the GIL
+unsafe impl Send for QueuedBorrow {}
+
+/// The fourth field holds the ids of removed cars and is only dropped with the arena,
+/// so views can tell when their car or the arena is gone without borrowing the arena
+///
+/// Separate arenas can be stepped from separate threads at the same time, since `step` releases the GIL.
+/// Using one arena from two threads at once raises a RuntimeError, because it stays borrowed while it steps.
+#[pyclass(module = "rocketsim.sim")]
+pub struct Arena(
+    pub UniquePtr<csim::Arena>,
+    pub GameMode,
+    Option<Py<ArenaRecorder>>,
+    Arc<RemovedCars>,
+    Option<Arc<GoalCallback>>,
+);
+
+/// The ids of the cars that were removed from an arena, RocketSim never reuses them
+pub type RemovedCars = Mutex<Vec<u32>>;
+
+// SAFETY: RocketSim's arenas don't depend on the thread they were made on, and pyo3 only hands out `&mut Arena` to one thread at a time.
+// The other fields are `Send` themselves, including the goal scored callback that RocketSim calls while stepping.
+unsafe impl Send for Arena {}
+
+/// What RocketSim's goal scored callback gets a pointer to
+///
+/// It's kept alive by the arena, and an exception from the callback is held until the step finishes,
+/// since it can't unwind through RocketSim.
+pub struct GoalCallback {
+    callback: PyObject,
+    error: Mutex<Option<PyErr>>,
+}
+
+/// The height of a car's center above the surface its wheels rest on, like at kickoff
+const CAR_REST_HEIGHT: f32 = 17.;
+
+impl Arena {
+    /// For views, which can check it without borrowing the arena
+    #[inline]
+    pub fn removed_cars(&self) -> Weak<RemovedCars> {
+        Arc::downgrade(&self.3)
+    }
+
+    /// Changes the car's state with `f`, raising an IndexError if there's no car with the id
+    fn modify_car(&mut self, id: u32, f: impl FnOnce(&mut csim::CarState)) -> PyResult<()> {
+        if !self.0.get_cars().contains(&id) {
+            return Err(PyIndexError::new_err(format!("No car with id {id}")));
+        }
+
+        let mut car = self.0.pin_mut().get_car(id);
+        f(&mut car);
+        self.0.pin_mut().set_car(id, car).map_err(|e| PyIndexError::new_err(e.to_string()))
+    }
+
+    /// Puts the car at rest on a surface, with its wheels down and its jump and flip available
+    fn rest_car(car: &mut csim::CarState, pos: Vec3, rot_mat: RotMat) {
+        car.pos = pos.into();
+        car.rot_mat = rot_mat.into();
+        car.vel = Vec3::ZERO.into();
+        car.ang_vel = Vec3::ZERO.into();
+        car.is_on_ground = true;
+        car.has_contact = true;
+        car.contact_normal = rot_mat.up.into();
+        Self::reset_jump(car);
+    }
+
+    /// Clears the jump, flip and auto flip state, leaving the car as if it never jumped
+    fn reset_jump(car: &mut csim::CarState) {
+        car.has_jumped = false;
+        car.has_double_jumped = false;
+        car.has_flipped = false;
+        car.is_jumping = false;
+        car.jump_time = 0.;
+        car.flip_time = 0.;
+        car.air_time_since_jump = 0.;
+        car.last_rel_dodge_torque = Vec3::ZERO.into();
+        car.is_auto_flipping = false;
+        car.auto_flip_timer = 0.;
+        car.auto_flip_torque_scale = 0.;
+    }
+
+    /// The fields and cars that `step_record` and `step_async` sample, with the cars defaulting to all of them
+    fn sample_targets(&self, fields: &[String], car_ids: Option<Vec<u32>>) -> PyResult<(Vec<RecordField>, Vec<u32>)> {
+        let fields = fields.iter().map(|name| RecordField::from_name(name)).collect::<PyResult<Vec<_>>>()?;
+        let car_ids = car_ids.unwrap_or_else(|| self.0.get_cars());
+
+        let cars = self.0.get_cars();
+        if let Some(id) = car_ids.iter().find(|id| !cars.contains(id)) {
+            return Err(PyIndexError::new_err(format!("No car with id {id}")));
+        }
+
+        Ok((fields, car_ids))
+    }
+
+    /// Adds the fields of the ball and then of each car to `samples`
+    fn sample(&mut self, fields: &[RecordField], car_ids: &[u32], samples: &mut Vec<f32>) {
+        let ball = self.0.pin_mut().get_ball();
+        let ball_rot = CRotMat::from(Mat3A::from_quat(Quat::from_array(self.0.get_ball_rotation())));
+        for field in fields {
+            field.extend(samples, ball.pos, ball.vel, ball.ang_vel, ball_rot);
+        }
+
+        for &id in car_ids {
+            let car = self.0.pin_mut().get_car(id);
+            for field in fields {
+                field.extend(samples, car.pos, car.vel, car.ang_vel, car.rot_mat);
+            }
+        }
+    }
+
+    /// Raises the first exception from the goal scored callback, after all the ticks were simulated
+    fn step_without_gil(&mut self, py: Python, ticks: i32) -> PyResult<()> {
+        // the whole arena is moved into the closure, since only `Arena` is `Send`
+        let arena = &mut *self;
+        py.allow_threads(move || arena.0.pin_mut().step(ticks));
+
+        self.take_goal_callback_error()
+    }
+
+    /// Raises the exception that the goal scored callback held until the step finished, if it raised one
+    fn take_goal_callback_error(&self) -> PyResult<()> {
+        match self.4.as_ref().and_then(|goal_callback| goal_callback.error.lock().unwrap().take()) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Calls `step` for one tick at a time until there's an event that's `wanted`, for `step_until`
+    fn step_until_event(
+        &mut self,
+        max_ticks: u32,
+        wanted: impl Fn(&ReplayEvent) -> bool,
+        mut step: impl FnMut(&mut Self) -> PyResult<()>,
+    ) -> PyResult<(u32, Option<ReplayEvent>)> {
+        let car_ids = self.0.get_cars();
+        let mut tracker = EventTracker::default();
+        let mut events = Vec::new();
+        tracker.track_arena(self.0.pin_mut(), &car_ids, self.1, &mut events);
+
+        for tick in 1..=max_ticks {
+            step(self)?;
+            tracker.track_arena(self.0.pin_mut(), &car_ids, self.1, &mut events);
+
+            if let Some(event) = events.drain(..).find(&wanted) {
+                return Ok((tick, Some(event)));
+            }
+        }
+
+        Ok((max_ticks, None))
+    }
+
+    fn raycast_dir(&self, origin: GVec3, direction: GVec3, max_dist: f32) -> PyResult<Option<(GVec3, GVec3, f32)>> {
+        let Some(dir) = direction.try_normalize() else {
+            return Err(PyValueError::new_err("The ray direction can't be zero"));
+        };
+
+        Ok(match self.1 {
+            GameMode::Soccar => raycast_soccar(origin, dir, max_dist),
+            GameMode::TheVoid => None,
+        })
+    }
+}
 
 impl PartialEq for Arena {
     fn eq(&self, other: &Self) -> bool {
@@ -995,8 +1683,9 @@ impl Arena {
     #[new]
     #[inline]
     #[pyo3(signature = (gamemode = GameMode::Soccar, tick_rate=120.))]
-    fn __new__(gamemode: GameMode, tick_rate: f32) -> Self {
-        Self(csim::Arena::new(gamemode.into(), tick_rate).within_unique_ptr())
+    pub fn __new__(gamemode: GameMode, tick_rate: f32) -> PyResult<Self> {
+        check_initialized(gamemode)?;
+        Ok(Self(csim::Arena::new(gamemode.into(), tick_rate).within_unique_ptr(), gamemode, None, Arc::default(), None))
     }
 
     #[inline]
@@ -1009,19 +1698,210 @@ impl Arena {
         self.0.get_tick_count()
     }
 
+    /// Releases the GIL while simulating, only taking it back to call the goal scored callback
+    pub fn step(&mut self, py: Python, ticks_to_simulate: Option<i32>) -> PyResult<()> {
+        let ticks_to_simulate = ticks_to_simulate.unwrap_or(1);
+
+        let Some(recorder) = self.2.as_ref().map(|recorder| recorder.clone_ref(py)) else {
+            return self.step_without_gil(py, ticks_to_simulate);
+        };
+
+        // step one tick at a time so the recorder sees every touch, demo and goal
+        let mut recorder = recorder.borrow_mut(py);
+        for _ in 0..ticks_to_simulate {
+            self.step_without_gil(py, 1)?;
+            recorder.on_tick(self.0.pin_mut(), self.1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Steps while changing controls at exact ticks, with each change applied before the tick at its offset is simulated
+    ///
+    /// Steps for `ticks_to_simulate` ticks, or until just after the last change if it's None.
+    #[pyo3(signature = (schedule, ticks_to_simulate=None))]
+    fn step_with_schedule(&mut self, py: Python, mut schedule: Vec<(u32, u32, CarControls)>, ticks_to_simulate: Option<u32>) -> PyResult<()> {
+        let ticks_to_simulate = match ticks_to_simulate {
+            Some(ticks) => ticks,
+            // saturating is enough, since anything over i32::MAX is rejected below
+            None => schedule.iter().map(|(offset, ..)| offset.saturating_add(1)).max().unwrap_or(0),
+        };
+
+        if i32::try_from(ticks_to_simulate).is_err() {
+            return Err(PyValueError::new_err(format!("Can't step {ticks_to_simulate} ticks, the most is {}", i32::MAX)));
+        }
+
+        let cars = self.0.get_cars();
+        for &(offset, id, _) in &schedule {
+            if !cars.contains(&id) {
+                return Err(PyIndexError::new_err(format!("No car with id {id}")));
+            }
+
+            if offset >= ticks_to_simulate {
+                return Err(PyValueError::new_err(format!("Tick offset {offset} is past the {ticks_to_simulate} ticks to simulate")));
+            }
+        }
+
+        // a stable sort, so later changes for the same tick and car win
+        schedule.sort_by_key(|(offset, ..)| *offset);
+
+        let mut tick = 0;
+        let mut changes = schedule.into_iter().peekable();
+        while tick < ticks_to_simulate {
+            while let Some((_, id, controls)) = changes.next_if(|(offset, ..)| *offset == tick) {
+                self.0
+                    .pin_mut()
+                    .set_car_controls(id, (&controls).into())
+                    .map_err(|e| PyIndexError::new_err(e.to_string()))?;
+            }
+
+            // step straight to the next change, which fits in an i32 since ticks_to_simulate does
+            let next_tick = changes.peek().map_or(ticks_to_simulate, |(offset, ..)| *offset);
+            self.step(py, Some((next_tick - tick) as i32))?;
+            tick = next_tick;
+        }
+
+        Ok(())
+    }
+
+    /// Steps while sampling the ball and cars every `every_n_ticks` ticks, returning an (N, 1 + cars, values) array
+    ///
+    /// The ball comes first, then the cars in the order of `car_ids`, which defaults to `get_cars`.
+    /// The values are the chosen fields in order, 3 for each vector and 9 for `rot_mat`.
+    #[pyo3(signature = (ticks_to_simulate, fields=vec!["pos".to_string(), "vel".to_string(), "rot_mat".to_string()], every_n_ticks=1, car_ids=None))]
+    fn step_record<'py>(
+        &mut self,
+        py: Python<'py>,
+        ticks_to_simulate: u32,
+        fields: Vec<String>,
+        every_n_ticks: u32,
+        car_ids: Option<Vec<u32>>,
+    ) -> PyResult<&'py PyArray3<f32>> {
+        if every_n_ticks == 0 {
+            return Err(PyValueError::new_err("every_n_ticks must be at least 1"));
+        }
+
+        if i32::try_from(ticks_to_simulate).is_err() {
+            return Err(PyValueError::new_err(format!("Can't step {ticks_to_simulate} ticks, the most is {}", i32::MAX)));
+        }
+
+        let (fields, car_ids) = self.sample_targets(&fields, car_ids)?;
+
+        let num_samples = (ticks_to_simulate / every_n_ticks) as usize;
+        let num_values = fields.iter().map(|field| field.num_values()).sum::<usize>();
+        let len = num_samples
+            .checked_mul(1 + car_ids.len())
+            .and_then(|len| len.checked_mul(num_values))
+            .ok_or_else(|| PyValueError::new_err("Too many samples to fit in an array"))?;
+        let mut samples = Vec::new();
+        samples
+            .try_reserve_exact(len)
+            .map_err(|_| PyMemoryError::new_err(format!("Not enough memory for {len} sampled values")))?;
+
+        // `every_n_ticks` only steps when there's at least one sample, so it's at most `ticks_to_simulate` and fits in an i32 too
+        for _ in 0..num_samples {
+            self.step(py, Some(every_n_ticks as i32))?;
+            self.sample(&fields, &car_ids, &mut samples);
+        }
+
+        // the ticks after the last sample
+        self.step(py, Some((ticks_to_simulate % every_n_ticks) as i32))?;
+
+        PyArray1::from_vec(py, samples).reshape([num_samples, 1 + car_ids.len(), num_values])
+    }
+
+    /// Steps until one of the chosen events happens or `max_ticks` ticks were simulated
+    ///
+    /// Returns the number of ticks simulated and the event that stopped it, or None if none did.
+    /// The GIL is only taken between ticks when a recorder is attached.
+    #[pyo3(signature = (max_ticks, on_goal=true, on_touch=false, on_demo=false))]
+    fn step_until(&mut self, py: Python, max_ticks: u32, on_goal: bool, on_touch: bool, on_demo: bool) -> PyResult<(u32, Option<ReplayEvent>)> {
+        if on_goal && self.1 != GameMode::Soccar {
+            return Err(PyValueError::new_err(format!("There are no goals in {:?}, so on_goal must be False", self.1)));
+        }
+
+        let wanted = move |event: &ReplayEvent| match event.kind {
+            ReplayEventKind::Goal => on_goal,
+            ReplayEventKind::Touch => on_touch,
+            ReplayEventKind::Demo => on_demo,
+        };
+
+        if self.2.is_some() {
+            return self.step_until_event(max_ticks, wanted, |arena| arena.step(py, Some(1)));
+        }
+
+        let arena = &mut *self;
+        let result = py.allow_threads(move || {
+            arena.step_until_event(max_ticks, wanted, |arena| {
+                arena.0.pin_mut().step(1);
+                Ok(())
+            })
+        });
+
+        self.take_goal_callback_error()?;
+        result
+    }
+
+    /// Steps on a worker thread, returning an asyncio future of a (1 + cars, values) array like one sample of `step_record`
+    ///
+    /// The arena is borrowed before this returns and until the future is done, so using it in the meantime raises a RuntimeError.
+    /// There's one worker for each CPU core, and steps are queued until one is free.
+    #[pyo3(signature = (ticks_to_simulate=1, fields=vec!["pos".to_string(), "vel".to_string(), "rot_mat".to_string()], car_ids=None))]
+    fn step_async(slf: &PyCell<Self>, py: Python, ticks_to_simulate: i32, fields: Vec<String>, car_ids: Option<Vec<u32>>) -> PyResult<PyObject> {
+        let event_loop: PyObject = py.import("asyncio")?.call_method0("get_running_loop")?.into();
+        let future: PyObject = event_loop.call_method0(py, "create_future")?;
+        let result_future = future.clone_ref(py);
+
+        let borrowed = QueuedBorrow::new(slf)?;
+        let (fields, car_ids) = borrowed.sample_targets(&fields, car_ids)?;
+
+        pool::spawn(move || {
+            Python::with_gil(|py| {
+                let mut arena = borrowed;
+
+                let result = arena.step(py, Some(ticks_to_simulate)).and_then(|()| {
+                    let mut sample = Vec::new();
+                    arena.sample(&fields, &car_ids, &mut sample);
+                    let num_values = sample.len() / (1 + car_ids.len());
+                    Ok(PyArray1::from_vec(py, sample).reshape([1 + car_ids.len(), num_values])?.into_py(py))
+                });
+                drop(arena);
+
+                let (value, is_error) = match result {
+                    Ok(observation) => (observation, false),
+                    Err(e) => (e.into_py(py), true),
+                };
+
+                // this only fails if the event loop was closed, and then nothing is waiting for the future
+                let _ = PyCFunction::new_closure(py, None, None, complete_future)
+                    .and_then(|callback| event_loop.call_method1(py, "call_soon_threadsafe", (callback, result_future, value, is_error)));
+            });
+        });
+
+        Ok(future)
+    }
+
+    /// Starts recording every step to the recorder's file, replacing any recorder that was already attached
+    fn attach_recorder(&mut self, py: Python, recorder: Py<ArenaRecorder>) -> PyResult<()> {
+        recorder.borrow_mut(py).start(self.0.pin_mut(), self.1)?;
+        self.2 = Some(recorder);
+        Ok(())
+    }
+
+    /// Stops recording, returning the recorder that was attached
     #[inline]
-    fn step(&mut self, ticks_to_simulate: Option<i32>) {
-        self.0.pin_mut().step(ticks_to_simulate.unwrap_or(1));
+    fn detach_recorder(&mut self) -> Option<Py<ArenaRecorder>> {
+        self.2.take()
     }
 
     #[inline]
-    fn get_ball(&mut self, py: Python) -> PyResult<Ball> {
-        self.0.pin_mut().get_ball().into_gil(py)
+    pub fn get_ball(&mut self) -> Ball {
+        self.0.pin_mut().get_ball().into()
     }
 
     #[inline]
-    fn set_ball(&mut self, py: Python, ball: Ball) {
-        self.0.pin_mut().set_ball(ball.remove_gil(py));
+    pub fn set_ball(&mut self, ball: Ball) {
+        self.0.pin_mut().set_ball(ball.into());
     }
 
     #[inline]
@@ -1029,9 +1909,11 @@ impl Arena {
         self.0.num_cars()
     }
 
-    #[inline]
-    fn add_car(&mut self, py: Python, team: Team, config: &CarConfig) -> u32 {
-        self.0.pin_mut().add_car(team.into(), &config.remove_gil(py))
+    /// Adds a car with the given config, raising a ValueError if the config would simulate badly
+    fn add_car(&mut self, team: Team, config: CarConfig) -> PyResult<u32> {
+        let config = config.into();
+        validate_config(&config)?;
+        Ok(self.0.pin_mut().add_car(team.into(), &config))
     }
 
     #[inline]
@@ -1053,18 +1935,131 @@ impl Arena {
     }
 
     #[inline]
-    fn get_car(&mut self, py: Python, id: u32) -> PyResult<Car> {
-        self.0.pin_mut().get_car(id).into_gil(py)
+    fn get_car(&mut self, id: u32) -> Car {
+        self.0.pin_mut().get_car(id).into()
+    }
+
+    #[inline]
+    fn remove_car(&mut self, id: u32) -> PyResult<()> {
+        self.0.pin_mut().remove_car(id).map_err(|e| PyIndexError::new_err(e.to_string()))?;
+        self.3.lock().unwrap().push(id);
+        Ok(())
+    }
+
+    /// A view of the car that reads its attributes from the arena on access, instead of copying the whole state like `get_car`
+    #[inline]
+    fn car_view(slf: &PyCell<Self>, id: u32) -> PyResult<CarView> {
+        CarView::new(slf, id)
+    }
+
+    /// A view of the ball that reads its attributes from the arena on access, instead of copying the whole state like `get_ball`
+    #[inline]
+    fn ball_view(slf: &PyCell<Self>) -> BallView {
+        BallView::new(slf)
+    }
+
+    /// The oriented bounding box of the car's hitbox
+    fn get_car_obb(&mut self, id: u32) -> PyResult<CarObb> {
+        if !self.0.get_cars().contains(&id) {
+            return Err(PyIndexError::new_err(format!("No car with id {id}")));
+        }
+
+        let car = self.0.pin_mut().get_car_info(id);
+        let (center, half_extents, corners) = hitbox_obb(car.state.pos.into(), car.state.rot_mat, &car.config);
+
+        Ok(CarObb {
+            center: center.into(),
+            half_extents: half_extents.into(),
+            rot_mat: car.state.rot_mat.into(),
+            corners: corners.into_iter().map(Vec3::from).collect(),
+        })
+    }
+
+    /// The hitbox corners of every car as an (N, 8, 3) array, with the cars in the order of `get_cars`
+    fn get_car_obbs<'py>(&mut self, py: Python<'py>) -> PyResult<&'py PyArray3<f32>> {
+        let corners = self
+            .0
+            .get_cars()
+            .into_iter()
+            .map(|id| {
+                let car = self.0.pin_mut().get_car_info(id);
+                let (_, _, corners) = hitbox_obb(car.state.pos.into(), car.state.rot_mat, &car.config);
+                corners.into_iter().map(|corner| corner.to_array().to_vec()).collect()
+            })
+            .collect::<Vec<Vec<Vec<f32>>>>();
+
+        if corners.is_empty() {
+            return Ok(PyArray3::zeros(py, [0, 8, 3], false));
+        }
+
+        Ok(PyArray3::from_vec3(py, &corners)?)
     }
 
     #[inline]
-    fn get_ball_rotation(&self, py: Python) -> PyResult<RotMat> {
-        Quat::from_array(self.0.get_ball_rotation()).into_gil(py)
+    pub fn get_ball_rotation(&self) -> RotMat {
+        Quat::from_array(self.0.get_ball_rotation()).into()
     }
 
     #[inline]
-    fn set_car(&mut self, py: Python, id: u32, car: Car) -> PyResult<()> {
-        self.0.pin_mut().set_car(id, car.remove_gil(py)).map_err(|e| PyIndexError::new_err(e.to_string()))
+    fn set_car(&mut self, id: u32, car: Car) -> PyResult<()> {
+        self.0.pin_mut().set_car(id, car.into()).map_err(|e| PyIndexError::new_err(e.to_string()))
+    }
+
+    /// Puts the car at rest on the ground at `pos.x` and `pos.y`, facing `yaw`
+    ///
+    /// `pos.z` is ignored, the car is always put at its resting height on the floor.
+    #[pyo3(signature = (id, pos, yaw=0.))]
+    pub fn place_car_on_ground(&mut self, id: u32, pos: Vec3, yaw: f32) -> PyResult<()> {
+        self.modify_car(id, |car| {
+            Self::rest_car(car, pos.with_z(CAR_REST_HEIGHT), RotMat::from_angles(0., yaw, 0.));
+        })
+    }
+
+    /// Puts the car at rest on a wall, with its wheels on the point `pos` and its nose pointing up the wall
+    ///
+    /// `normal` points out of the wall, so it's `Vec3(-1, 0, 0)` for the wall at positive x.
+    fn place_car_on_wall(&mut self, id: u32, pos: Vec3, normal: Vec3) -> PyResult<()> {
+        let Some(up) = GVec3::from(normal).try_normalize() else {
+            return Err(PyValueError::new_err("The wall normal can't be zero"));
+        };
+
+        let Some(forward) = (GVec3::Z - up * up.z).try_normalize() else {
+            return Err(PyValueError::new_err("The wall normal can't be vertical, use place_car_on_ground for the floor"));
+        };
+
+        let rot_mat = RotMat {
+            forward: forward.into(),
+            right: up.cross(forward).into(),
+            up: up.into(),
+        };
+
+        self.modify_car(id, |car| Self::rest_car(car, (GVec3::from(pos) + up * CAR_REST_HEIGHT).into(), rot_mat))
+    }
+
+    /// Puts the car in the air as if it just finished its first jump, so it still has its flip unless `has_flip` is False
+    #[pyo3(signature = (id, pos, rot_mat, vel=Vec3::ZERO, ang_vel=Vec3::ZERO, has_flip=true))]
+    pub fn place_car_airborne(&mut self, id: u32, pos: Vec3, rot_mat: RotMat, vel: Vec3, ang_vel: Vec3, has_flip: bool) -> PyResult<()> {
+        self.modify_car(id, |car| {
+            car.pos = pos.into();
+            car.rot_mat = rot_mat.into();
+            car.vel = vel.into();
+            car.ang_vel = ang_vel.into();
+            car.is_on_ground = false;
+            car.has_contact = false;
+            car.contact_normal = Vec3::ZERO.into();
+            Self::reset_jump(car);
+            car.has_jumped = true;
+            car.has_double_jumped = !has_flip;
+        })
+    }
+
+    /// Sets the car's boost, raising a ValueError if it isn't between 0 and 100
+    pub fn set_car_boost(&mut self, id: u32, amount: f32) -> PyResult<()> {
+        if !(0. ..=100.).contains(&amount) {
+            return Err(PyValueError::new_err(format!("Boost must be between 0 and 100, not {amount}")));
+        }
+
+        self.modify_car(id, |car| car.boost = amount)
     }
 
     #[inline]
@@ -1091,26 +2086,31 @@ impl Arena {
     }
 
     #[inline]
-    fn get_game_state(&mut self, py: Python) -> PyResult<GameState> {
+    pub fn get_game_state(&mut self, py: Python) -> PyResult<GameState> {
         Ok(GameState {
             tick_rate: self.0.get_tick_rate(),
             tick_count: self.0.get_tick_count(),
-            ball: new_gil!(Ball, py, self.0.pin_mut().get_ball()),
-            ball_rot: new_gil!(RotMat, py, Quat::from_array(self.0.pin_mut().get_ball_rotation())),
+            ball: self.0.pin_mut().get_ball().into(),
+            ball_rot: Quat::from_array(self.0.pin_mut().get_ball_rotation()).into(),
             cars: self
                 .0
                 .GetCars()
                 .iter()
-                .map(|&car_id| self.0.pin_mut().get_car_info(car_id).into_gil(py).and_then(|car: CarInfo| Py::new(py, car)))
-                .collect::<Result<_, _>>()?,
-            pads: self
-                .0
-                .iter_pads()
-                .map(|pad| pad.into_gil(py).and_then(|pad: BoostPad| Py::new(py, pad)))
+                .map(|&car_id| Py::new(py, CarInfo::from(self.0.pin_mut().get_car_info(car_id))))
                 .collect::<Result<_, _>>()?,
+            pads: self.0.iter_pads().map(|pad| Py::new(py, BoostPad::from(pad))).collect::<Result<_, _>>()?,
         })
     }
 
+    /// A stable hash of the physics state, arenas given the same `GameState` and controls will keep producing the same hash
+    ///
+    /// The tick count and ball rotation aren't included since `set_game_state` can't restore them,
+    /// the ball is a sphere so its rotation never affects the physics
+    #[inline]
+    fn state_hash(&mut self) -> u64 {
+        state_hash(self.0.pin_mut().get_game_state())
+    }
+
     #[inline]
     fn set_game_state(&mut self, py: Python, game_state: GameState) -> PyResult<()> {
         self.0
@@ -1119,23 +2119,88 @@ impl Arena {
             .map_err(|e| PyIndexError::new_err(e.to_string()))
     }
 
-    #[inline]
-    fn set_goal_scored_callback(&mut self, py: Python, callback: PyObject) {
+    /// Draws the field, boost pads, ball and car hitboxes from above as an SVG image
+    #[pyo3(signature = (width=600, height=800))]
+    fn render_topdown(&mut self, width: u32, height: u32) -> PyResult<String> {
+        check_image_size(width, height)?;
+        Ok(render_topdown(&self.0.pin_mut().get_game_state(), self.1, width, height))
+    }
+
+    /// Casts a ray against the arena's static collision geometry, returning None if nothing was hit within max_dist
+    fn raycast(&self, origin: Vec3, direction: Vec3, max_dist: f32) -> PyResult<Option<RayHit>> {
+        Ok(self.raycast_dir(origin.into(), direction.into(), max_dist)?.map(|(pos, normal, distance)| RayHit {
+            pos: pos.into(),
+            normal: normal.into(),
+            distance,
+        }))
+    }
+
+    /// Casts N rays given as (N, 3) arrays of origins and directions
+    ///
+    /// Returns an (N, 7) array of hit position, normal and distance, with rows of NaN for rays that didn't hit anything
+    fn raycast_batch<'py>(&self, py: Python<'py>, origins: PyReadonlyArray2<f32>, directions: PyReadonlyArray2<f32>, max_dist: f32) -> PyResult<&'py PyArray2<f32>> {
+        let (origins, directions) = (origins.as_array(), directions.as_array());
+        if origins.ncols() != 3 || origins.shape() != directions.shape() {
+            return Err(PyValueError::new_err(format!(
+                "origins and directions must both have the shape (N, 3), found {:?} and {:?}",
+                origins.shape(),
+                directions.shape()
+            )));
+        }
+
+        let hits = origins
+            .rows()
+            .into_iter()
+            .zip(directions.rows())
+            .map(|(origin, direction)| {
+                let origin = GVec3::new(origin[0], origin[1], origin[2]);
+                let direction = GVec3::new(direction[0], direction[1], direction[2]);
+
+                Ok(match self.raycast_dir(origin, direction, max_dist)? {
+                    Some((pos, normal, distance)) => vec![pos.x, pos.y, pos.z, normal.x, normal.y, normal.z, distance],
+                    None => vec![f32::NAN; 7],
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        if hits.is_empty() {
+            return Ok(PyArray2::zeros(py, [0, 7], false));
+        }
+
+        Ok(PyArray2::from_vec2(py, &hits)?)
+    }
+
+    /// Calls `callback(team)` when a goal is scored, an exception it raises is raised by the step after it finishes
+    fn set_goal_scored_callback(&mut self, callback: PyObject) {
+        let goal_callback = Arc::new(GoalCallback {
+            callback,
+            error: Mutex::new(None),
+        });
+
         self.0.pin_mut().set_goal_scored_callback(
             |_, team, user_info| {
-                Python::with_gil(|_| {
+                // SAFETY: the arena keeps the callback alive until it's replaced or the arena is dropped, which can't happen while it steps
+                let goal_callback = unsafe { &*(user_info as *const GoalCallback) };
+
+                Python::with_gil(|py| {
                     let team = match team {
                         csim::Team::BLUE => Team::Blue,
                         csim::Team::ORANGE => Team::Orange,
                     };
 
-                    unsafe {
-                        let callback = user_info as *const PyAny;
-                        (*callback).call1((team,)).unwrap();
+                    // only the first exception is raised, so the callback isn't called again after one
+                    if goal_callback.error.lock().unwrap().is_some() {
+                        return;
                     }
-                })
+
+                    if let Err(error) = goal_callback.callback.call1(py, (team,)) {
+                        *goal_callback.error.lock().unwrap() = Some(error);
+                    }
+                });
             },
-            callback.as_ref(py) as *const PyAny as usize,
+            Arc::as_ptr(&goal_callback) as usize,
         );
+
+        self.4 = Some(goal_callback);
     }
 }
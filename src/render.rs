@@ -0,0 +1,214 @@
+use std::fmt::Write;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rocketsim_rs::{
+    math::Vec3 as CVec3,
+    sim::{CarState, Team},
+    GameState as CGameState,
+};
+
+use crate::{
+    field_info::{Field, SOCCAR_EXTENT_X, SOCCAR_EXTENT_Y, SOCCAR_GOAL_DEPTH},
+    python::GameMode,
+};
+
+/// Space around the field in the image, in uu
+const MARGIN: f32 = 200.;
+const BALL_RADIUS: f32 = 91.25;
+const BIG_PAD_RADIUS: f32 = 160.;
+const SMALL_PAD_RADIUS: f32 = 100.;
+
+const BACKGROUND_COLOR: &str = "#1e1e1e";
+const FIELD_COLOR: &str = "#2f5d34";
+const LINE_COLOR: &str = "#e0e0e0";
+const BLUE_COLOR: &str = "#2c7be5";
+const ORANGE_COLOR: &str = "#f28c28";
+const PAD_ACTIVE_COLOR: &str = "#ffd23f";
+const PAD_INACTIVE_COLOR: &str = "#6b6b6b";
+
+/// Maps field coordinates to image coordinates, with orange's side at the top
+struct Projection {
+    scale: f32,
+    offset_x: f32,
+    offset_y: f32,
+}
+
+impl Projection {
+    fn new(width: u32, height: u32) -> Self {
+        let half_x = SOCCAR_EXTENT_X + MARGIN;
+        let half_y = SOCCAR_EXTENT_Y + SOCCAR_GOAL_DEPTH + MARGIN;
+        let scale = (width as f32 / (half_x * 2.)).min(height as f32 / (half_y * 2.));
+
+        Self {
+            scale,
+            offset_x: width as f32 / 2.,
+            offset_y: height as f32 / 2.,
+        }
+    }
+
+    #[inline]
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.offset_x + x * self.scale, self.offset_y - y * self.scale)
+    }
+
+    #[inline]
+    fn length(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+
+    fn points(&self, points: impl IntoIterator<Item = (f32, f32)>) -> String {
+        points
+            .into_iter()
+            .map(|(x, y)| {
+                let (x, y) = self.point(x, y);
+                format!("{x:.1},{y:.1}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn team_color(team: Team) -> &'static str {
+    if team == Team::BLUE {
+        BLUE_COLOR
+    } else {
+        ORANGE_COLOR
+    }
+}
+
+/// The corners of the field's floor, going around counter-clockwise
+fn field_outline(field: &Field) -> Vec<(f32, f32)> {
+    let corner_x = field.corner_offset - field.extent_y;
+    let corner_y = field.corner_offset - field.extent_x;
+
+    vec![
+        (field.extent_x, -corner_y),
+        (field.extent_x, corner_y),
+        (corner_x, field.extent_y),
+        (-corner_x, field.extent_y),
+        (-field.extent_x, corner_y),
+        (-field.extent_x, -corner_y),
+        (-corner_x, -field.extent_y),
+        (corner_x, -field.extent_y),
+    ]
+}
+
+/// The corners of the car's hitbox projected onto the floor, and the point at the front of the hitbox
+fn car_footprint(car: &CarState, hitbox_size: CVec3, hitbox_offset: CVec3) -> ([(f32, f32); 4], (f32, f32)) {
+    let forward = car.rot_mat.forward;
+    let right = car.rot_mat.right;
+    let up = car.rot_mat.up;
+
+    let center_x = car.pos.x + forward.x * hitbox_offset.x + right.x * hitbox_offset.y + up.x * hitbox_offset.z;
+    let center_y = car.pos.y + forward.y * hitbox_offset.x + right.y * hitbox_offset.y + up.y * hitbox_offset.z;
+
+    let corner = |length: f32, width: f32| (center_x + forward.x * length + right.x * width, center_y + forward.y * length + right.y * width);
+
+    let half_length = hitbox_size.x / 2.;
+    let half_width = hitbox_size.y / 2.;
+
+    (
+        [
+            corner(half_length, half_width),
+            corner(half_length, -half_width),
+            corner(-half_length, -half_width),
+            corner(-half_length, half_width),
+        ],
+        corner(half_length, 0.),
+    )
+}
+
+pub fn check_image_size(width: u32, height: u32) -> PyResult<()> {
+    if width == 0 || height == 0 {
+        return Err(PyValueError::new_err("The image width and height must be at least 1"));
+    }
+
+    Ok(())
+}
+
+/// Draws the field, boost pads, ball and cars from above as an SVG
+pub fn render_topdown(game_state: &CGameState, gamemode: GameMode, width: u32, height: u32) -> String {
+    let field = Field::from_gamemode(gamemode);
+    let projection = Projection::new(width, height);
+    let line_width = projection.length(20.).max(1.);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    writeln!(svg, r#"<rect width="{width}" height="{height}" fill="{BACKGROUND_COLOR}"/>"#).unwrap();
+
+    if gamemode == GameMode::Soccar {
+        for goal in &field.goals {
+            let side = goal.center.y.signum();
+            let back_y = goal.center.y + goal.depth * side;
+            let half_width = goal.width / 2.;
+            let color = team_color(goal.team.into());
+
+            writeln!(
+                svg,
+                r#"<polygon points="{}" fill="{color}" fill-opacity="0.35" stroke="{color}" stroke-width="{line_width:.1}"/>"#,
+                projection.points([(-half_width, goal.center.y), (half_width, goal.center.y), (half_width, back_y), (-half_width, back_y)])
+            )
+            .unwrap();
+        }
+
+        writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{FIELD_COLOR}" stroke="{LINE_COLOR}" stroke-width="{line_width:.1}"/>"#,
+            projection.points(field_outline(&field))
+        )
+        .unwrap();
+
+        let (left, center_y) = projection.point(-field.extent_x, 0.);
+        let (right, _) = projection.point(field.extent_x, 0.);
+        writeln!(
+            svg,
+            r#"<line x1="{left:.1}" y1="{center_y:.1}" x2="{right:.1}" y2="{center_y:.1}" stroke="{LINE_COLOR}" stroke-opacity="0.5" stroke-width="{line_width:.1}"/>"#
+        )
+        .unwrap();
+    }
+
+    for pad in &game_state.pads {
+        let (x, y) = projection.point(pad.position.x, pad.position.y);
+        let radius = projection.length(if pad.is_big { BIG_PAD_RADIUS } else { SMALL_PAD_RADIUS });
+        let color = if pad.state.is_active { PAD_ACTIVE_COLOR } else { PAD_INACTIVE_COLOR };
+        writeln!(svg, r#"<circle cx="{x:.1}" cy="{y:.1}" r="{radius:.1}" fill="{color}"/>"#).unwrap();
+    }
+
+    for car in &game_state.cars {
+        if car.state.is_demoed {
+            continue;
+        }
+
+        let (corners, front) = car_footprint(&car.state, car.config.hitbox_size, car.config.hitbox_pos_offset);
+        let color = team_color(car.team);
+        let (center_x, center_y) = projection.point(car.state.pos.x, car.state.pos.y);
+        let (front_x, front_y) = projection.point(front.0, front.1);
+
+        writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{color}" stroke="{LINE_COLOR}" stroke-width="{line_width:.1}"/>"#,
+            projection.points(corners)
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<line x1="{center_x:.1}" y1="{center_y:.1}" x2="{front_x:.1}" y2="{front_y:.1}" stroke="{LINE_COLOR}" stroke-width="{line_width:.1}"/>"#
+        )
+        .unwrap();
+    }
+
+    let (ball_x, ball_y) = projection.point(game_state.ball.pos.x, game_state.ball.pos.y);
+    let ball_radius = projection.length(BALL_RADIUS).max(1.);
+    writeln!(
+        svg,
+        r#"<circle cx="{ball_x:.1}" cy="{ball_y:.1}" r="{ball_radius:.1}" fill="white" stroke="black" stroke-width="{line_width:.1}"/>"#
+    )
+    .unwrap();
+
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,629 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    pin::Pin,
+};
+
+use pyo3::{
+    exceptions::{PyIndexError, PyValueError},
+    prelude::*,
+};
+use rocketsim_rs::{bytes::ToBytes, sim as csim, GameState as CGameState};
+
+use crate::{
+    base::IntoGil,
+    diff::{Differ, FieldDiff},
+    field_info::SOCCAR_BALL_SCORE_THRESHOLD_Y,
+    python::{Arena, GameMode, Team},
+    render::{check_image_size, render_topdown},
+    state::{checked_game_state_from_bytes, GameState},
+};
+
+const REPLAY_MAGIC: &[u8; 4] = b"RSRP";
+const REPLAY_VERSION: u16 = 1;
+const EVENT_NUM_BYTES: usize = 1 + 8 + 1 + 4;
+const NO_CAR_ID: u32 = u32::MAX;
+/// Frames longer than this are treated as corrupt instead of allocating whatever length the file claims
+const MAX_FRAME_NUM_BYTES: usize = 1 << 24;
+
+#[pyclass(module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayEventKind {
+    Goal,
+    Touch,
+    Demo,
+}
+
+#[pymethods]
+impl ReplayEventKind {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!("ReplayEventKind.{self:?}")
+    }
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim.sim")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub kind: ReplayEventKind,
+    /// The tick that the event happened on
+    pub tick_count: u64,
+    /// The scoring team for goals, otherwise the team of the car
+    pub team: Team,
+    /// The car that touched the ball or was demoed, or the last car to touch the ball for goals
+    pub car_id: Option<u32>,
+}
+
+impl ReplayEvent {
+    fn to_bytes(self) -> [u8; EVENT_NUM_BYTES] {
+        let mut bytes = [0; EVENT_NUM_BYTES];
+        bytes[0] = self.kind as u8;
+        bytes[1..9].copy_from_slice(&self.tick_count.to_le_bytes());
+        bytes[9] = self.team as u8;
+        bytes[10..].copy_from_slice(&self.car_id.unwrap_or(NO_CAR_ID).to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        let kind = match bytes[0] {
+            0 => ReplayEventKind::Goal,
+            1 => ReplayEventKind::Touch,
+            2 => ReplayEventKind::Demo,
+            kind => return Err(PyValueError::new_err(format!("Invalid replay event kind {kind}"))),
+        };
+
+        let team = match bytes[9] {
+            0 => Team::Blue,
+            1 => Team::Orange,
+            team => return Err(PyValueError::new_err(format!("Invalid replay event team {team}"))),
+        };
+
+        let car_id = u32::from_le_bytes([bytes[10], bytes[11], bytes[12], bytes[13]]);
+
+        Ok(Self {
+            kind,
+            tick_count: u64::from_le_bytes(bytes[1..9].try_into().unwrap()),
+            team,
+            car_id: (car_id != NO_CAR_ID).then_some(car_id),
+        })
+    }
+}
+
+#[pymethods]
+impl ReplayEvent {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!(
+            "ReplayEvent(kind={}, tick_count={}, team={}, car_id={})",
+            self.kind.__repr__(),
+            self.tick_count,
+            self.team.__repr__(),
+            self.car_id.map_or("None".to_string(), |id| id.to_string())
+        )
+    }
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim.sim")]
+#[derive(Clone, Debug)]
+pub struct ReplayFrame {
+    pub tick_count: u64,
+    /// The full state after the tick, including the controls each car used in `state.last_controls`
+    pub game_state: Py<GameState>,
+    /// The events since the previous frame
+    pub events: Vec<ReplayEvent>,
+}
+
+#[pymethods]
+impl ReplayFrame {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+/// What the tracker last saw of a car, to detect touches and demos
+#[derive(Clone, Copy, Debug)]
+struct CarTracker {
+    last_hit_tick: Option<u64>,
+    is_demoed: bool,
+}
+
+/// Finds the goals, touches and demos between game states, for `ArenaRecorder` and `Arena.step_until`
+#[derive(Debug, Default)]
+pub struct EventTracker {
+    cars: HashMap<u32, CarTracker>,
+    last_touch: Option<u32>,
+    ball_in_goal: bool,
+}
+
+impl EventTracker {
+    /// Adds the events since the last game state that was tracked to `events`
+    ///
+    /// Cars that weren't in the last game state are only tracked, so the first game state never has touches or demos.
+    pub fn track(&mut self, game_state: &CGameState, gamemode: GameMode, events: &mut Vec<ReplayEvent>) {
+        for car in &game_state.cars {
+            self.track_car(car.id, car.team.into(), &car.state, game_state.tick_count, events);
+        }
+
+        self.track_ball(game_state.ball.pos.y, game_state.tick_count, gamemode, events);
+    }
+
+    /// Like `track`, but reads only the ball and the cars with the given ids from the arena instead of the whole game state
+    pub fn track_arena(&mut self, mut arena: Pin<&mut csim::Arena>, car_ids: &[u32], gamemode: GameMode, events: &mut Vec<ReplayEvent>) {
+        let tick_count = arena.get_tick_count();
+
+        for &id in car_ids {
+            let team = arena.get_car_team(id).into();
+            // RocketSim only hands out whole car states, which are still much smaller than a game state
+            let state = arena.as_mut().get_car(id);
+            self.track_car(id, team, &state, tick_count, events);
+        }
+
+        let ball_y = arena.get_ball().pos.y;
+        self.track_ball(ball_y, tick_count, gamemode, events);
+    }
+
+    fn track_car(&mut self, id: u32, team: Team, state: &csim::CarState, tick_count: u64, events: &mut Vec<ReplayEvent>) {
+        let hit = &state.ball_hit_info;
+        let last_hit_tick = hit.is_valid.then_some(hit.tick_count_when_hit);
+
+        if let Some(tracker) = self.cars.get(&id) {
+            if last_hit_tick.is_some() && last_hit_tick != tracker.last_hit_tick {
+                self.last_touch = Some(id);
+                events.push(ReplayEvent {
+                    kind: ReplayEventKind::Touch,
+                    tick_count: hit.tick_count_when_hit,
+                    team,
+                    car_id: Some(id),
+                });
+            }
+
+            if state.is_demoed && !tracker.is_demoed {
+                events.push(ReplayEvent {
+                    kind: ReplayEventKind::Demo,
+                    tick_count,
+                    team,
+                    car_id: Some(id),
+                });
+            }
+        }
+
+        self.cars.insert(
+            id,
+            CarTracker {
+                last_hit_tick,
+                is_demoed: state.is_demoed,
+            },
+        );
+    }
+
+    fn track_ball(&mut self, ball_y: f32, tick_count: u64, gamemode: GameMode, events: &mut Vec<ReplayEvent>) {
+        if let GameMode::Soccar = gamemode {
+            let ball_in_goal = ball_y.abs() > SOCCAR_BALL_SCORE_THRESHOLD_Y;
+
+            if ball_in_goal && !self.ball_in_goal {
+                events.push(ReplayEvent {
+                    kind: ReplayEventKind::Goal,
+                    tick_count,
+                    // the ball going into the positive y goal is a goal for blue
+                    team: if ball_y > 0. { Team::Blue } else { Team::Orange },
+                    car_id: self.last_touch,
+                });
+            }
+
+            self.ball_in_goal = ball_in_goal;
+        }
+    }
+}
+
+/// Writes the state of an `Arena` to a replay file while it's attached with `Arena.attach_recorder`
+///
+/// The file starts with the `RSRP` magic, the version and `every_n_ticks`, followed by the frames.
+/// Each frame is its length, the events since the last frame and the `GameState` in the rocketsim_rs `bin` format.
+#[pyclass(module = "rocketsim.sim")]
+pub struct ArenaRecorder {
+    writer: Option<BufWriter<File>>,
+    every_n_ticks: u32,
+    ticks_since_frame: u32,
+    num_frames: u64,
+    events: Vec<ReplayEvent>,
+    tracker: EventTracker,
+}
+
+impl ArenaRecorder {
+    fn writer(&mut self) -> PyResult<&mut BufWriter<File>> {
+        self.writer.as_mut().ok_or_else(|| PyValueError::new_err("The recorder has been closed"))
+    }
+
+    fn write_frame(&mut self, game_state: &CGameState) -> PyResult<()> {
+        let events = self.events.drain(..).flat_map(ReplayEvent::to_bytes).collect::<Vec<_>>();
+        let game_state = game_state.to_bytes();
+        let num_events = (events.len() / EVENT_NUM_BYTES) as u32;
+
+        let writer = self.writer()?;
+        writer.write_all(&((4 + events.len() + game_state.len()) as u32).to_le_bytes())?;
+        writer.write_all(&num_events.to_le_bytes())?;
+        writer.write_all(&events)?;
+        writer.write_all(&game_state)?;
+
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    /// Records the current state as the first frame, without looking for events
+    pub fn start(&mut self, mut arena: Pin<&mut csim::Arena>, gamemode: GameMode) -> PyResult<()> {
+        let game_state = arena.as_mut().get_game_state();
+        self.tracker.track(&game_state, gamemode, &mut self.events);
+        self.events.clear();
+        self.ticks_since_frame = 0;
+        self.write_frame(&game_state)
+    }
+
+    /// Looks for events after a tick and writes a frame every `every_n_ticks` ticks
+    pub fn on_tick(&mut self, mut arena: Pin<&mut csim::Arena>, gamemode: GameMode) -> PyResult<()> {
+        self.writer()?;
+
+        let game_state = arena.as_mut().get_game_state();
+        self.tracker.track(&game_state, gamemode, &mut self.events);
+
+        self.ticks_since_frame += 1;
+        if self.ticks_since_frame >= self.every_n_ticks {
+            self.ticks_since_frame = 0;
+            self.write_frame(&game_state)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl ArenaRecorder {
+    #[new]
+    #[pyo3(signature = (path, every_n_ticks=1))]
+    fn __new__(path: PathBuf, every_n_ticks: u32) -> PyResult<Self> {
+        if every_n_ticks == 0 {
+            return Err(PyValueError::new_err("every_n_ticks must be at least 1"));
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+        writer.write_all(&every_n_ticks.to_le_bytes())?;
+
+        Ok(Self {
+            writer: Some(writer),
+            every_n_ticks,
+            ticks_since_frame: 0,
+            num_frames: 0,
+            events: Vec::new(),
+            tracker: EventTracker::default(),
+        })
+    }
+
+    #[getter]
+    #[inline]
+    fn every_n_ticks(&self) -> u32 {
+        self.every_n_ticks
+    }
+
+    /// The number of frames written so far
+    #[getter]
+    #[inline]
+    fn num_frames(&self) -> u64 {
+        self.num_frames
+    }
+
+    #[inline]
+    fn flush(&mut self) -> PyResult<()> {
+        Ok(self.writer()?.flush()?)
+    }
+
+    /// Flushes and closes the file, events since the last frame are dropped
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterates over the frames of a replay file written by `ArenaRecorder`
+#[pyclass(module = "rocketsim.sim")]
+pub struct ReplayReader {
+    reader: BufReader<File>,
+    version: u16,
+    every_n_ticks: u32,
+}
+
+impl ReplayReader {
+    /// Reads the next frame's events and game state, or None at the end of the file
+    pub fn read_frame(&mut self) -> PyResult<Option<(Vec<ReplayEvent>, CGameState)>> {
+        let mut len = [0; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let corrupt = || PyValueError::new_err("Corrupt replay frame");
+
+        let len = u32::from_le_bytes(len) as usize;
+        if !(4..=MAX_FRAME_NUM_BYTES).contains(&len) {
+            return Err(corrupt());
+        }
+
+        let mut bytes = vec![0; len];
+        self.reader.read_exact(&mut bytes)?;
+
+        let num_events = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        let events_end = num_events.saturating_mul(EVENT_NUM_BYTES).saturating_add(4);
+        if bytes.len() < events_end {
+            return Err(corrupt());
+        }
+
+        let events = bytes[4..events_end].chunks_exact(EVENT_NUM_BYTES).map(ReplayEvent::from_bytes).collect::<PyResult<_>>()?;
+        let game_state = checked_game_state_from_bytes(&bytes[events_end..]).map_err(|_| corrupt())?;
+
+        Ok(Some((events, game_state)))
+    }
+}
+
+#[pymethods]
+impl ReplayReader {
+    #[new]
+    fn __new__(path: PathBuf) -> PyResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut header = [0; 10];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != REPLAY_MAGIC {
+            return Err(PyValueError::new_err("Not a RocketSim replay file"));
+        }
+
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version > REPLAY_VERSION {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported replay version {version}, the newest supported version is {REPLAY_VERSION}"
+            )));
+        }
+
+        Ok(Self {
+            reader,
+            version,
+            every_n_ticks: u32::from_le_bytes([header[6], header[7], header[8], header[9]]),
+        })
+    }
+
+    #[getter]
+    #[inline]
+    fn version(&self) -> u16 {
+        self.version
+    }
+
+    #[getter]
+    #[inline]
+    fn every_n_ticks(&self) -> u32 {
+        self.every_n_ticks
+    }
+
+    #[inline]
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    /// Renders each remaining frame with `Arena.render_topdown` to numbered SVG files in the folder, returning their paths
+    #[pyo3(signature = (folder, width=600, height=800, gamemode=GameMode::Soccar))]
+    fn render_frames(&mut self, folder: PathBuf, width: u32, height: u32, gamemode: GameMode) -> PyResult<Vec<PathBuf>> {
+        check_image_size(width, height)?;
+        fs::create_dir_all(&folder)?;
+
+        let mut paths = Vec::new();
+        while let Some((_, game_state)) = self.read_frame()? {
+            let path = folder.join(format!("frame_{:06}.svg", paths.len()));
+            fs::write(&path, render_topdown(&game_state, gamemode, width, height))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<ReplayFrame>> {
+        let Some((events, game_state)) = self.read_frame()? else {
+            return Ok(None);
+        };
+
+        let tick_count = game_state.tick_count;
+        let game_state: GameState = game_state.into_gil(py)?;
+
+        Ok(Some(ReplayFrame {
+            tick_count,
+            game_state: Py::new(py, game_state)?,
+            events,
+        }))
+    }
+}
+
+#[pyclass(get_all, frozen, module = "rocketsim.sim")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayDivergence {
+    /// The recorded tick where the states first differed
+    pub tick_count: u64,
+    /// The path to the field that differed, like `cars[1].state.pos.x`, using the recorded car ids
+    pub field: String,
+    pub recorded: f32,
+    pub simulated: f32,
+}
+
+#[pymethods]
+impl ReplayDivergence {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!(
+            "ReplayDivergence(tick_count={}, field={:?}, recorded={}, simulated={})",
+            self.tick_count, self.field, self.recorded, self.simulated
+        )
+    }
+}
+
+/// Finds the first field that differs by more than the tolerance, comparing the same fields as `GameState.diff`
+///
+/// The ball's rotation is left out, since the arena was seeded with `set_game_state`, which can't restore it.
+fn first_divergence(recorded: &CGameState, simulated: &CGameState, car_ids: &HashMap<u32, u32>, tolerance: f32) -> Option<FieldDiff> {
+    let mut differ = Differ::new(tolerance);
+    differ.car_ids = car_ids.clone();
+    differ.ball_rot = false;
+
+    let cars = recorded.cars.iter().map(|car| {
+        let sim_car = car_ids.get(&car.id).and_then(|id| simulated.cars.iter().find(|sim_car| sim_car.id == *id));
+        (format!("cars[{}]", car.id), car, sim_car)
+    });
+    differ.game_state(recorded, simulated, cars);
+
+    differ.diffs.into_iter().next()
+}
+
+/// Re-simulates a replay file written by `ArenaRecorder`, checking that the simulation matches the recording
+///
+/// The arena is seeded from the first frame, then the controls from each frame are used for the ticks leading up to it.
+/// Controls that changed between frames can't be recovered, so replays that should match exactly need `every_n_ticks=1`.
+#[pyclass(unsendable, module = "rocketsim.sim")]
+pub struct ReplayPlayer {
+    frames: Vec<CGameState>,
+    /// Recorded car id -> car id in the re-simulated arena
+    car_ids: HashMap<u32, u32>,
+    frame_index: usize,
+    #[pyo3(get)]
+    tolerance: f32,
+    #[pyo3(get)]
+    arena: Py<Arena>,
+}
+
+#[pymethods]
+impl ReplayPlayer {
+    #[new]
+    #[pyo3(signature = (path, gamemode=GameMode::Soccar, tolerance=0.01))]
+    fn __new__(py: Python, path: PathBuf, gamemode: GameMode, tolerance: f32) -> PyResult<Self> {
+        let mut reader = ReplayReader::__new__(path)?;
+        let mut frames = Vec::new();
+        while let Some((_, game_state)) = reader.read_frame()? {
+            frames.push(game_state);
+        }
+
+        let Some(first) = frames.first() else {
+            return Err(PyValueError::new_err("The replay doesn't have any frames"));
+        };
+
+        let mut arena = Arena::__new__(gamemode, first.tick_rate)?;
+        if arena.0.num_pads() != first.pads.len() {
+            return Err(PyValueError::new_err(format!(
+                "The replay has {} boost pads but the {gamemode:?} arena has {}, it was recorded in a different game mode",
+                first.pads.len(),
+                arena.0.num_pads()
+            )));
+        }
+
+        let mut car_ids = HashMap::new();
+        for car in &first.cars {
+            let id = arena.0.pin_mut().add_car(car.team, &car.config);
+            arena.0.pin_mut().set_car(id, car.state).map_err(|e| PyIndexError::new_err(e.to_string()))?;
+            car_ids.insert(car.id, id);
+        }
+
+        for (i, pad) in first.pads.iter().enumerate() {
+            arena.0.pin_mut().set_pad_state(i, pad.state);
+        }
+
+        arena.0.pin_mut().set_ball(first.ball);
+
+        Ok(Self {
+            frames,
+            car_ids,
+            frame_index: 0,
+            tolerance,
+            arena: Py::new(py, arena)?,
+        })
+    }
+
+    #[getter]
+    #[inline]
+    fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The index of the last frame that was played, 0 is the frame the arena was seeded from
+    #[getter]
+    #[inline]
+    fn frame_index(&self) -> usize {
+        self.frame_index
+    }
+
+    #[getter]
+    #[inline]
+    fn is_finished(&self) -> bool {
+        self.frame_index + 1 >= self.frames.len()
+    }
+
+    /// Simulates up to the next frame like `Arena.step`, returning how it diverged from the recording or None if it matched
+    fn step(&mut self, py: Python) -> PyResult<Option<ReplayDivergence>> {
+        if self.is_finished() {
+            return Err(PyIndexError::new_err("The replay has already finished"));
+        }
+
+        let (prev, next) = (&self.frames[self.frame_index], &self.frames[self.frame_index + 1]);
+        let mut arena = self.arena.borrow_mut(py);
+
+        for car in &next.cars {
+            if let Some(&id) = self.car_ids.get(&car.id) {
+                arena
+                    .0
+                    .pin_mut()
+                    .set_car_controls(id, car.state.last_controls)
+                    .map_err(|e| PyIndexError::new_err(e.to_string()))?;
+            }
+        }
+
+        let ticks = next.tick_count.saturating_sub(prev.tick_count);
+        let ticks = i32::try_from(ticks).map_err(|_| PyValueError::new_err(format!("Can't step {ticks} ticks between frames, the most is {}", i32::MAX)))?;
+        // the ticks were simulated even if the goal scored callback raised
+        let stepped = arena.step(py, Some(ticks));
+        self.frame_index += 1;
+        stepped?;
+
+        let simulated = arena.0.pin_mut().get_game_state();
+        Ok(first_divergence(next, &simulated, &self.car_ids, self.tolerance).map(|diff| ReplayDivergence {
+            tick_count: next.tick_count,
+            field: diff.field,
+            recorded: diff.old,
+            simulated: diff.new,
+        }))
+    }
+
+    /// Plays the rest of the replay, stopping at and returning the first divergence
+    fn run(&mut self, py: Python) -> PyResult<Option<ReplayDivergence>> {
+        while !self.is_finished() {
+            if let Some(divergence) = self.step(py)? {
+                return Ok(Some(divergence));
+            }
+        }
+
+        Ok(None)
+    }
+}
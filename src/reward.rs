@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use numpy::{PyArray1, PyArray2};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rocketsim_rs::{
+    glam_ext::glam::{Vec3 as GVec3, Vec3A},
+    GameState as CGameState,
+};
+
+use crate::{
+    field_info::opponent_goal,
+    python::{Arena, GameMode, Team},
+    replay::{EventTracker, ReplayEvent, ReplayEventKind},
+};
+
+const CAR_MAX_SPEED: f32 = 2300.;
+const BALL_MAX_SPEED: f32 = 6000.;
+
+/// A term that `RewardFunction` computes for every car
+#[pyclass(module = "rocketsim.rewards")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RewardTerm {
+    /// The car's velocity toward the ball, from -1 to 1 at max car speed
+    VelocityToBall,
+    /// The ball's velocity toward the opponent's goal, from -1 to 1 at max ball speed
+    BallVelocityToGoal,
+    /// 1 if the car touched the ball since the last `compute`, however many times it did
+    Touch,
+    /// 1 for a goal by the car's team, -1 for a goal against it
+    Goal,
+    /// How much the car's boost went up since the last `compute`, from 0 to 1 for a full tank
+    BoostPickup,
+    /// How much the car's boost went down since the last `compute`, from 0 to 1 for a full tank
+    BoostUsage,
+    /// How directly the car's nose points at the ball, from -1 to 1
+    FacingBall,
+    /// 1 for a demo by the car, -1 for being demoed
+    Demo,
+}
+
+#[pymethods]
+impl RewardTerm {
+    #[inline]
+    fn __str__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    #[inline]
+    pub fn __repr__(&self) -> String {
+        format!("RewardTerm.{self:?}")
+    }
+}
+
+/// A weighted sum of reward terms, computed from the arena's state after each step
+///
+/// Touches, goals, boost and demos are found by comparing with the state at the last `compute`,
+/// so each `RewardFunction` should only be used with one arena.
+/// Nothing is tracked between calls, so the fewer ticks there are between them, the closer these terms are:
+/// - Boost is the net change, so boost picked up and used in the same interval cancel out
+/// - A goal counts when the ball is past the goal line and wasn't at the last call, so one that's reset before the call is missed
+/// - A demo is credited to the car whose last bump was the demoed car while its bump cooldown is still running,
+///   so it's missed if that car bumped another one since, or if the cooldown ran out before the call
+#[pyclass(module = "rocketsim.rewards")]
+pub struct RewardFunction {
+    #[pyo3(get)]
+    terms: Vec<(RewardTerm, f32)>,
+    tracker: EventTracker,
+    events: Vec<ReplayEvent>,
+    /// Car id -> boost at the last `compute`
+    boosts: HashMap<u32, f32>,
+    /// Car -> unweighted terms from the last `compute`
+    last_terms: Vec<Vec<f32>>,
+}
+
+impl RewardFunction {
+    /// Updates the tracked events and boost, returning the state they were tracked from
+    fn track(&mut self, arena: &mut Arena) -> CGameState {
+        let game_state = arena.0.pin_mut().get_game_state();
+        self.events.clear();
+        self.tracker.track(&game_state, arena.1, &mut self.events);
+        game_state
+    }
+
+    fn term(&self, term: RewardTerm, game_state: &CGameState, car_index: usize, gamemode: GameMode) -> f32 {
+        let car = &game_state.cars[car_index];
+        let team = Team::from(car.team);
+        let (car_pos, ball_pos) = (Vec3A::from(car.state.pos), Vec3A::from(game_state.ball.pos));
+        let to_ball = (ball_pos - car_pos).normalize_or_zero();
+
+        let count_events = |kind: ReplayEventKind, f: &dyn Fn(&ReplayEvent) -> f32| self.events.iter().filter(|event| event.kind == kind).map(f).sum();
+
+        match term {
+            RewardTerm::VelocityToBall => Vec3A::from(car.state.vel).dot(to_ball) / CAR_MAX_SPEED,
+            RewardTerm::BallVelocityToGoal => opponent_goal(team, gamemode).map_or(0., |goal| {
+                let to_goal = (Vec3A::from(GVec3::from(goal.center)) - ball_pos).normalize_or_zero();
+                Vec3A::from(game_state.ball.vel).dot(to_goal) / BALL_MAX_SPEED
+            }),
+            RewardTerm::Touch => count_events(ReplayEventKind::Touch, &|event| f32::from(u8::from(event.car_id == Some(car.id)))),
+            RewardTerm::Goal => count_events(ReplayEventKind::Goal, &|event| if event.team == team { 1. } else { -1. }),
+            RewardTerm::BoostPickup => self.boosts.get(&car.id).map_or(0., |boost| (car.state.boost - boost).max(0.) / 100.),
+            RewardTerm::BoostUsage => self.boosts.get(&car.id).map_or(0., |boost| (boost - car.state.boost).max(0.) / 100.),
+            RewardTerm::FacingBall => Vec3A::from(car.state.rot_mat.forward).dot(to_ball),
+            RewardTerm::Demo => count_events(ReplayEventKind::Demo, &|event| {
+                if event.car_id == Some(car.id) {
+                    -1.
+                } else if event.car_id == Some(car.state.other_car_id) && car.state.cooldown_timer > 0. {
+                    // the car that bumped the demoed car last is the one that demoed it
+                    1.
+                } else {
+                    0.
+                }
+            }),
+        }
+    }
+}
+
+#[pymethods]
+impl RewardFunction {
+    #[new]
+    fn __new__(terms: Vec<(RewardTerm, f32)>) -> PyResult<Self> {
+        if terms.is_empty() {
+            return Err(PyValueError::new_err("A RewardFunction needs at least one term"));
+        }
+
+        Ok(Self {
+            terms,
+            tracker: EventTracker::default(),
+            events: Vec::new(),
+            boosts: HashMap::new(),
+            last_terms: Vec::new(),
+        })
+    }
+
+    /// Starts tracking from the arena's current state without computing rewards, like after setting a new state
+    fn reset(&mut self, mut arena: PyRefMut<Arena>) {
+        self.tracker = EventTracker::default();
+        let game_state = self.track(&mut arena);
+        self.boosts = game_state.cars.iter().map(|car| (car.id, car.state.boost)).collect();
+        self.last_terms.clear();
+    }
+
+    /// The reward of each car since the last call, in the order of `Arena.get_cars`
+    fn compute<'py>(&mut self, py: Python<'py>, mut arena: PyRefMut<Arena>) -> &'py PyArray1<f32> {
+        let game_state = self.track(&mut arena);
+
+        self.last_terms = (0..game_state.cars.len())
+            .map(|i| self.terms.iter().map(|&(term, _)| self.term(term, &game_state, i, arena.1)).collect())
+            .collect();
+        self.boosts = game_state.cars.iter().map(|car| (car.id, car.state.boost)).collect();
+
+        let rewards = self
+            .last_terms
+            .iter()
+            .map(|terms| terms.iter().zip(&self.terms).map(|(value, (_, weight))| value * weight).sum())
+            .collect();
+
+        PyArray1::from_vec(py, rewards)
+    }
+
+    /// The unweighted terms from the last `compute` as a (cars, terms) array, for logging what the reward is made of
+    #[getter]
+    fn last_terms<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray2<f32>> {
+        let values = self.last_terms.iter().flatten().copied().collect();
+        PyArray1::from_vec(py, values).reshape([self.last_terms.len(), self.terms.len()])
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rocketsim_rs::{autocxx::prelude::*, bytes::ToBytes, cxx::UniquePtr, sim as csim};
+
+use crate::{meshes::check_initialized, python::GameMode, state::checked_game_state_from_bytes};
+
+/// How long blocking socket calls wait before checking for Ctrl+C
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Requests larger than this are rejected instead of allocated
+const MAX_MESSAGE_LEN: usize = 1 << 24;
+/// How many ticks a step simulates between checks for Ctrl+C
+const STEP_CHUNK_TICKS: u32 = 1200;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+#[repr(u8)]
+enum Command {
+    NewArena,
+    RemoveArena,
+    AddCar,
+    RemoveCar,
+    SetControls,
+    Step,
+    GetState,
+    SetState,
+    Reset,
+}
+
+impl TryFrom<u8> for Command {
+    type Error = String;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        Ok(match byte {
+            0 => Self::NewArena,
+            1 => Self::RemoveArena,
+            2 => Self::AddCar,
+            3 => Self::RemoveCar,
+            4 => Self::SetControls,
+            5 => Self::Step,
+            6 => Self::GetState,
+            7 => Self::SetState,
+            8 => Self::Reset,
+            _ => return Err(format!("Unknown command {byte}")),
+        })
+    }
+}
+
+/// Why a request failed
+enum RequestError {
+    /// Sent back to the client, which can keep going
+    Client(String),
+    /// Stops the server, like Ctrl+C during a long step
+    Server(PyErr),
+}
+
+impl From<String> for RequestError {
+    #[inline]
+    fn from(message: String) -> Self {
+        Self::Client(message)
+    }
+}
+
+impl From<PyErr> for RequestError {
+    #[inline]
+    fn from(e: PyErr) -> Self {
+        Self::Server(e)
+    }
+}
+
+/// A client's RocketSim arena
+struct ServerArena(UniquePtr<csim::Arena>);
+
+// SAFETY: like `python::Arena`, RocketSim's arenas don't depend on the thread they were made on,
+// and a session only ever uses its arenas from one thread at a time.
+unsafe impl Send for ServerArena {}
+
+/// Reads little endian values from the body of a request
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("Request is too short".to_string());
+        }
+
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.0)
+    }
+}
+
+/// The arenas owned by one client, addressed by their index
+#[derive(Default)]
+struct Session {
+    arenas: Vec<Option<ServerArena>>,
+}
+
+impl Session {
+    fn arena(&mut self, reader: &mut Reader) -> Result<&mut UniquePtr<csim::Arena>, String> {
+        Ok(&mut self.server_arena(reader)?.0)
+    }
+
+    fn server_arena(&mut self, reader: &mut Reader) -> Result<&mut ServerArena, String> {
+        let index = reader.u32()?;
+        self.arenas
+            .get_mut(index as usize)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| format!("No arena with index {index}"))
+    }
+
+    fn handle(&mut self, py: Python, request: &[u8]) -> Result<Vec<u8>, RequestError> {
+        let mut reader = Reader(request);
+
+        match Command::try_from(reader.u8()?)? {
+            Command::NewArena => {
+                let gamemode = match reader.u8()? {
+                    0 => GameMode::Soccar,
+                    1 => GameMode::TheVoid,
+                    gamemode => return Err(format!("Unknown game mode {gamemode}").into()),
+                };
+                let tick_rate = reader.f32()?;
+                check_initialized(gamemode).map_err(|e| e.value(py).to_string())?;
+
+                let arena = ServerArena(csim::Arena::new(gamemode.into(), tick_rate).within_unique_ptr());
+                let index = match self.arenas.iter().position(Option::is_none) {
+                    Some(index) => {
+                        self.arenas[index] = Some(arena);
+                        index
+                    }
+                    None => {
+                        self.arenas.push(Some(arena));
+                        self.arenas.len() - 1
+                    }
+                };
+
+                Ok((index as u32).to_le_bytes().to_vec())
+            }
+            Command::RemoveArena => {
+                let index = reader.u32()? as usize;
+                match self.arenas.get_mut(index).and_then(Option::take) {
+                    Some(_) => Ok(Vec::new()),
+                    None => Err(format!("No arena with index {index}").into()),
+                }
+            }
+            Command::AddCar => {
+                let arena = self.arena(&mut reader)?;
+                let team = match reader.u8()? {
+                    0 => csim::Team::BLUE,
+                    1 => csim::Team::ORANGE,
+                    team => return Err(format!("Unknown team {team}").into()),
+                };
+                let config = match reader.u8()? {
+                    0 => csim::CarConfig::octane(),
+                    1 => csim::CarConfig::dominus(),
+                    2 => csim::CarConfig::plank(),
+                    3 => csim::CarConfig::breakout(),
+                    4 => csim::CarConfig::hybrid(),
+                    5 => csim::CarConfig::merc(),
+                    preset => return Err(format!("Unknown car preset {preset}").into()),
+                };
+
+                Ok(arena.pin_mut().add_car(team, config).to_le_bytes().to_vec())
+            }
+            Command::RemoveCar => {
+                let arena = self.arena(&mut reader)?;
+                arena.pin_mut().remove_car(reader.u32()?).map_err(|e| e.to_string())?;
+                Ok(Vec::new())
+            }
+            Command::SetControls => {
+                let arena = self.arena(&mut reader)?;
+                let controls = (0..reader.u32()?)
+                    .map(|_| {
+                        Ok((
+                            reader.u32()?,
+                            csim::CarControls {
+                                throttle: reader.f32()?,
+                                steer: reader.f32()?,
+                                pitch: reader.f32()?,
+                                yaw: reader.f32()?,
+                                roll: reader.f32()?,
+                                jump: reader.bool()?,
+                                boost: reader.bool()?,
+                                handbrake: reader.bool()?,
+                            },
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                arena.pin_mut().set_all_controls(&controls).map_err(|e| e.to_string())?;
+                Ok(Vec::new())
+            }
+            Command::Step => {
+                let arena = self.server_arena(&mut reader)?;
+                let mut ticks = reader.u32()?;
+
+                // release the GIL like `Arena.step`, and check for Ctrl+C between chunks so a huge step can be interrupted
+                while ticks > 0 {
+                    let chunk = ticks.min(STEP_CHUNK_TICKS);
+                    let stepped = &mut *arena;
+                    py.allow_threads(move || stepped.0.pin_mut().step(chunk as i32));
+                    ticks -= chunk;
+                    py.check_signals()?;
+                }
+
+                Ok(arena.0.pin_mut().get_game_state().to_bytes())
+            }
+            Command::GetState => Ok(self.arena(&mut reader)?.pin_mut().get_game_state().to_bytes()),
+            Command::SetState => {
+                let arena = self.arena(&mut reader)?;
+                let game_state = checked_game_state_from_bytes(reader.rest())?;
+
+                // RocketSim doesn't bounds check pad indices
+                if game_state.pads.len() > arena.num_pads() {
+                    return Err(format!("Game state has {} boost pads, the arena only has {}", game_state.pads.len(), arena.num_pads()).into());
+                }
+
+                arena.pin_mut().set_game_state(&game_state).map_err(|e| e.to_string())?;
+                Ok(Vec::new())
+            }
+            Command::Reset => {
+                let arena = self.arena(&mut reader)?;
+                let seed = reader.i32()?;
+                arena.pin_mut().reset_to_random_kickoff(if seed < 0 { None } else { Some(seed) });
+                Ok(arena.pin_mut().get_game_state().to_bytes())
+            }
+        }
+    }
+}
+
+/// Fills `buf` from the stream, returning false if the client disconnected
+fn read_exact(py: Python, stream: &mut TcpStream, buf: &mut [u8]) -> PyResult<bool> {
+    let mut read = 0;
+
+    while read < buf.len() {
+        match py.allow_threads(|| stream.read(&mut buf[read..])) {
+            Ok(0) => return Ok(false),
+            Ok(n) => read += n,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) => {
+                py.check_signals()?;
+            }
+            Err(_) => return Ok(false),
+        }
+    }
+
+    Ok(true)
+}
+
+fn write_response(py: Python, stream: &mut TcpStream, status: u8, body: &[u8]) -> io::Result<()> {
+    let mut response = Vec::with_capacity(body.len() + 5);
+    response.extend((body.len() as u32 + 1).to_le_bytes());
+    response.push(status);
+    response.extend(body);
+    py.allow_threads(|| stream.write_all(&response))
+}
+
+/// Answers requests from one client until it disconnects
+///
+/// Errors talking to the client end its session without stopping the server
+fn serve_client(py: Python, mut stream: TcpStream) -> PyResult<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let mut session = Session::default();
+    let mut len = [0; 4];
+
+    while read_exact(py, &mut stream, &mut len)? {
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_MESSAGE_LEN {
+            let _ = write_response(py, &mut stream, STATUS_ERROR, format!("Request of {len} bytes is too large").as_bytes());
+            return Ok(());
+        }
+
+        let mut request = vec![0; len];
+        if !read_exact(py, &mut stream, &mut request)? {
+            return Ok(());
+        }
+
+        let written = match session.handle(py, &request) {
+            Ok(body) => write_response(py, &mut stream, STATUS_OK, &body),
+            Err(RequestError::Client(message)) => write_response(py, &mut stream, STATUS_ERROR, message.as_bytes()),
+            Err(RequestError::Server(e)) => return Err(e),
+        };
+
+        if written.is_err() {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Hosts arenas for clients in other languages over TCP, blocking until `max_connections` clients have disconnected or Ctrl+C is pressed
+///
+/// Clients are served one at a time, so a second client waits until the first one disconnects.
+/// Each client gets its own arenas that are removed when it disconnects.
+/// See the README for the protocol.
+#[pyfunction]
+#[pyo3(signature = (port, host="127.0.0.1", max_connections=None))]
+pub fn serve(py: Python, port: u16, host: &str, max_connections: Option<usize>) -> PyResult<()> {
+    if max_connections == Some(0) {
+        return Err(PyValueError::new_err("max_connections must be at least 1"));
+    }
+
+    let listener = TcpListener::bind((host, port))?;
+    listener.set_nonblocking(true)?;
+
+    let mut connections = 0;
+    while !matches!(max_connections, Some(max) if connections >= max) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                connections += 1;
+                serve_client(py, stream)?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                py.allow_threads(|| thread::sleep(POLL_INTERVAL));
+                py.check_signals()?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::hash_map::RandomState,
+    f32::consts::{FRAC_PI_2, PI},
+    hash::{BuildHasher, Hasher},
+};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rocketsim_rs::glam_ext::glam::Vec3 as GVec3;
+
+use crate::{
+    base::{RotMat, Vec3},
+    field_info::{distance_to_wall, own_goal, Field},
+    packet::BALL_RADIUS,
+    python::{Arena, Ball, GameMode, Team},
+    state::GameState,
+};
+
+/// How far cars are kept from the walls, enough for any preset's hitbox at any yaw
+const CAR_WALL_MARGIN: f32 = 150.;
+/// How far apart random cars and the ball are kept, so nothing starts clipped into anything else
+const SPACING: f32 = 300.;
+/// The closest crowded fields shrink `SPACING` to, which still keeps any preset's hitbox clear of other cars and the ball
+const MIN_SPACING: f32 = 200.;
+/// How much `SPACING` shrinks by each time a placement fails `MAX_ATTEMPTS` times
+const SPACING_STEP: f32 = 25.;
+const MAX_ATTEMPTS: u32 = 100;
+/// How high airborne cars can be above the floor and below the ceiling
+const AIRBORNE_MARGIN: f32 = 300.;
+const MAX_AIRBORNE_SPEED: f32 = 1500.;
+/// How far in front of the goal line the goalie starts
+const GOALIE_OFFSET: f32 = 150.;
+
+/// SplitMix64, so a seed gives the same states on every platform
+struct Rng(u64);
+
+impl Rng {
+    #[inline]
+    fn new(seed: Option<u64>) -> Self {
+        Self(seed.unwrap_or_else(|| RandomState::new().build_hasher().finish()))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform between `min` and `max`
+    #[inline]
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (self.next_u64() >> 40) as f32 / (1 << 24) as f32 * (max - min)
+    }
+
+    #[inline]
+    fn chance(&mut self, probability: f32) -> bool {
+        self.range(0., 1.) < probability
+    }
+
+    /// A uniformly random unit vector
+    fn direction(&mut self) -> GVec3 {
+        loop {
+            let vec = GVec3::new(self.range(-1., 1.), self.range(-1., 1.), self.range(-1., 1.));
+            if vec.length_squared() <= 1. {
+                if let Some(dir) = vec.try_normalize() {
+                    return dir;
+                }
+            }
+        }
+    }
+
+    /// A position inside `field` that's `wall_margin` from the walls and `SPACING` from everything in `taken`
+    ///
+    /// The spacing shrinks towards `MIN_SPACING` when the field is crowded, and a ValueError is raised if even that doesn't fit
+    fn field_pos(&mut self, field: &Field, wall_margin: f32, min_z: f32, max_z: f32, taken: &[Vec3]) -> PyResult<Vec3> {
+        let mut spacing = SPACING;
+        loop {
+            for _ in 0..MAX_ATTEMPTS {
+                let pos = Vec3::new(
+                    self.range(-field.extent_x, field.extent_x),
+                    self.range(-field.extent_y, field.extent_y),
+                    self.range(min_z, max_z),
+                );
+
+                if distance_to_wall(pos, field.gamemode) >= wall_margin && taken.iter().all(|&other| GVec3::from(pos).distance(other.into()) >= spacing) {
+                    return Ok(pos);
+                }
+            }
+
+            if spacing <= MIN_SPACING {
+                return Err(PyValueError::new_err(format!(
+                    "Couldn't find a spot {MIN_SPACING} away from the ball and the other cars, there are too many to fit"
+                )));
+            }
+
+            spacing = (spacing - SPACING_STEP).max(MIN_SPACING);
+        }
+    }
+}
+
+/// The field of the arena's game mode, or a ValueError if it has no walls to place things inside of
+fn bounded_field(gamemode: GameMode) -> PyResult<Field> {
+    let field = Field::from_gamemode(gamemode);
+    if !(field.extent_x.is_finite() && field.extent_y.is_finite() && field.height.is_finite()) {
+        return Err(PyValueError::new_err(format!("Random positions need a game mode with walls, not {gamemode:?}")));
+    }
+
+    Ok(field)
+}
+
+fn set_random_ball(arena: &mut Arena, rng: &mut Rng, field: &Field, max_speed: f32) -> PyResult<Vec3> {
+    let pos = rng.field_pos(field, BALL_RADIUS, BALL_RADIUS, field.height - BALL_RADIUS, &[])?;
+    let vel = rng.direction() * rng.range(0., max_speed);
+
+    arena.set_ball(Ball {
+        pos,
+        vel: vel.into(),
+        ang_vel: Vec3::ZERO,
+    });
+
+    Ok(pos)
+}
+
+fn set_random_cars(arena: &mut Arena, rng: &mut Rng, field: &Field, ids: Vec<u32>, airborne_chance: f32, mut taken: Vec<Vec3>) -> PyResult<()> {
+    for id in ids {
+        let yaw = rng.range(-PI, PI);
+
+        let pos = if rng.chance(airborne_chance) {
+            let pos = rng.field_pos(field, CAR_WALL_MARGIN, AIRBORNE_MARGIN, field.height - AIRBORNE_MARGIN, &taken)?;
+            let rot_mat = RotMat::from_angles(rng.range(-FRAC_PI_2, FRAC_PI_2), yaw, rng.range(-PI, PI));
+            let vel = rng.direction() * rng.range(0., MAX_AIRBORNE_SPEED);
+            arena.place_car_airborne(id, pos, rot_mat, vel.into(), Vec3::ZERO, true)?;
+            pos
+        } else {
+            let pos = rng.field_pos(field, CAR_WALL_MARGIN, 0., 0., &taken)?;
+            arena.place_car_on_ground(id, pos, yaw)?;
+            pos
+        };
+
+        taken.push(pos);
+    }
+
+    Ok(())
+}
+
+fn set_random_boost(arena: &mut Arena, rng: &mut Rng, min_boost: f32, max_boost: f32) -> PyResult<()> {
+    if !(0. <= min_boost && min_boost <= max_boost && max_boost <= 100.) {
+        return Err(PyValueError::new_err(format!(
+            "Boost range must be within 0 to 100 with min_boost <= max_boost, not {min_boost} to {max_boost}"
+        )));
+    }
+
+    for id in arena.0.get_cars() {
+        arena.set_car_boost(id, rng.range(min_boost, max_boost))?;
+    }
+
+    Ok(())
+}
+
+/// Puts the ball somewhere inside the field, moving in a random direction at up to `max_speed`
+///
+/// Raises a ValueError if the game mode has no walls
+#[pyfunction]
+#[pyo3(signature = (arena, seed=None, max_speed=2000.))]
+pub fn random_ball(py: Python, mut arena: PyRefMut<Arena>, seed: Option<u64>, max_speed: f32) -> PyResult<GameState> {
+    let field = bounded_field(arena.1)?;
+    set_random_ball(&mut arena, &mut Rng::new(seed), &field, max_speed)?;
+    arena.get_game_state(py)
+}
+
+/// Puts every car somewhere inside the field and away from the ball, on the ground or in the air
+///
+/// Raises a ValueError if the game mode has no walls, or if there are too many cars to keep apart
+#[pyfunction]
+#[pyo3(signature = (arena, seed=None, airborne_chance=0.2))]
+pub fn random_cars(py: Python, mut arena: PyRefMut<Arena>, seed: Option<u64>, airborne_chance: f32) -> PyResult<GameState> {
+    let field = bounded_field(arena.1)?;
+    let ball = arena.get_ball();
+    let ids = arena.0.get_cars();
+    set_random_cars(&mut arena, &mut Rng::new(seed), &field, ids, airborne_chance, vec![ball.pos])?;
+    arena.get_game_state(py)
+}
+
+/// Gives every car a random amount of boost between `min_boost` and `max_boost`
+#[pyfunction]
+#[pyo3(signature = (arena, seed=None, min_boost=0., max_boost=100.))]
+pub fn random_boost(py: Python, mut arena: PyRefMut<Arena>, seed: Option<u64>, min_boost: f32, max_boost: f32) -> PyResult<GameState> {
+    set_random_boost(&mut arena, &mut Rng::new(seed), min_boost, max_boost)?;
+    arena.get_game_state(py)
+}
+
+/// `random_ball`, `random_cars` and `random_boost` with their defaults, from one seed
+///
+/// Raises a ValueError in the same cases as `random_cars`
+#[pyfunction]
+#[pyo3(signature = (arena, seed=None))]
+pub fn random_state(py: Python, mut arena: PyRefMut<Arena>, seed: Option<u64>) -> PyResult<GameState> {
+    let field = bounded_field(arena.1)?;
+    let mut rng = Rng::new(seed);
+    let ball = set_random_ball(&mut arena, &mut rng, &field, 2000.)?;
+    let ids = arena.0.get_cars();
+    set_random_cars(&mut arena, &mut rng, &field, ids, 0.2, vec![ball])?;
+    set_random_boost(&mut arena, &mut rng, 0., 100.)?;
+    arena.get_game_state(py)
+}
+
+/// Resets the ball, cars and boost pads to one of RocketSim's kickoff positions
+#[pyfunction]
+#[pyo3(signature = (arena, seed=None))]
+pub fn kickoff(py: Python, mut arena: PyRefMut<Arena>, seed: Option<u64>) -> PyResult<GameState> {
+    // RocketSim takes a non-negative i32 seed, or picks one itself
+    let seed = seed.map(|seed| (Rng::new(Some(seed)).next_u64() >> 33) as i32);
+    arena.0.pin_mut().reset_to_random_kickoff(seed);
+    arena.get_game_state(py)
+}
+
+/// Shoots the ball at the goal that `team` defends, with that team's first car on its goal line and the other cars on the ground
+///
+/// Raises a ValueError if the game mode doesn't have goals
+#[pyfunction]
+#[pyo3(signature = (arena, team=Team::Blue, seed=None))]
+pub fn goalie(py: Python, mut arena: PyRefMut<Arena>, team: Team, seed: Option<u64>) -> PyResult<GameState> {
+    let Some(goal) = own_goal(team, arena.1) else {
+        return Err(PyValueError::new_err("goalie needs a game mode with goals"));
+    };
+    let field = bounded_field(arena.1)?;
+
+    let mut rng = Rng::new(seed);
+    let side = goal.center.y.signum();
+
+    let pos = Vec3::new(rng.range(-2500., 2500.), side * rng.range(1500., 3500.), rng.range(BALL_RADIUS, 500.));
+    let target = GVec3::new(
+        rng.range(BALL_RADIUS - goal.width / 2., goal.width / 2. - BALL_RADIUS),
+        goal.center.y,
+        rng.range(BALL_RADIUS, goal.height - BALL_RADIUS),
+    );
+    let vel = (target - GVec3::from(pos)).normalize() * rng.range(1500., 3000.);
+
+    arena.set_ball(Ball {
+        pos,
+        vel: vel.into(),
+        ang_vel: Vec3::ZERO,
+    });
+
+    let mut taken = vec![pos];
+    let mut ids = arena.0.get_cars();
+    if let Some(i) = ids.iter().position(|&id| Team::from(arena.0.pin_mut().get_car_info(id).team) == team) {
+        let goalie_pos = Vec3::new(0., goal.center.y - side * GOALIE_OFFSET, 0.);
+        // face out of the goal
+        arena.place_car_on_ground(ids.remove(i), goalie_pos, -side * FRAC_PI_2)?;
+        taken.push(goalie_pos);
+    }
+
+    set_random_cars(&mut arena, &mut rng, &field, ids, 0., taken)?;
+    set_random_boost(&mut arena, &mut rng, 0., 100.)?;
+    arena.get_game_state(py)
+}
@@ -1,41 +1,77 @@
-use pyo3::prelude::*;
-use rocketsim_rs::{glam_ext::glam::Quat, BoostPad as CBoostPad, CarInfo as CCarInfo, GameState as CGameState};
+use pyo3::{exceptions::PyValueError, prelude::*, pyclass::CompareOp, types::PyBytes};
+use rocketsim_rs::{
+    bytes::{FromBytes, FromBytesExact, ToBytes},
+    glam_ext::glam::Quat,
+    BoostPad as CBoostPad, CarInfo as CCarInfo, GameState as CGameState,
+};
 
 use crate::{
-    base::{repr_bool, FromGil, PyDefault, RemoveGil, RotMat, Vec3},
-    new_gil, new_gil_default,
-    python::{Ball, BoostPadState, Car, CarConfig, Team},
+    base::{repr_bool, richcmp_eq, FromGil, IntoGil, PyEq, RemoveGil, RotMat, RotMatRef, Vec3, Vec3Ref},
+    diff::{Differ, FieldDiff, DEFAULT_TOLERANCE},
+    python::{Ball, BallRef, BoostPadState, BoostPadStateRef, Car, CarConfig, CarConfigRef, CarRef, Team},
 };
 
-#[pyclass(get_all, set_all, module = "rocketsim")]
-#[derive(Clone, Debug)]
+/// Byte offset of the team in each car of the rocketsim_rs binary format, after the car's id
+const CAR_TEAM_OFFSET: usize = 4;
+
+/// Parses a game state in the rocketsim_rs binary format from untrusted bytes
+///
+/// `CGameState::from_bytes` panics on the wrong number of bytes or a team that isn't 0 or 1,
+/// which would abort the process, so both are checked first
+pub fn checked_game_state_from_bytes(bytes: &[u8]) -> Result<CGameState, String> {
+    if bytes.len() < CGameState::MIN_NUM_BYTES || CGameState::get_num_bytes(bytes) != bytes.len() {
+        return Err("Game state has the wrong number of bytes".to_string());
+    }
+
+    let cars_start = bytes.len() - CGameState::read_num_cars(bytes) * CCarInfo::NUM_BYTES;
+    for (i, car) in bytes[cars_start..].chunks_exact(CCarInfo::NUM_BYTES).enumerate() {
+        let team = car[CAR_TEAM_OFFSET];
+        if team > 1 {
+            return Err(format!("Game state has an invalid team {team} for car {i}"));
+        }
+    }
+
+    Ok(CGameState::from_bytes(bytes))
+}
+
+#[pyclass(set_all, module = "rocketsim")]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CarInfo {
+    #[pyo3(get)]
     pub id: u32,
+    #[pyo3(get)]
     pub team: Team,
-    pub state: Py<Car>,
-    pub config: Py<CarConfig>,
+    pub state: Car,
+    pub config: CarConfig,
 }
 
-impl FromGil<CCarInfo> for CarInfo {
+impl PyEq for CarInfo {
     #[inline]
-    fn from_gil(py: Python, info: CCarInfo) -> PyResult<Self> {
-        Ok(Self {
+    fn py_eq(&self, _py: Python, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl From<CCarInfo> for CarInfo {
+    #[inline]
+    fn from(info: CCarInfo) -> Self {
+        Self {
             id: info.id,
             team: info.team.into(),
-            state: new_gil!(Car, py, info.state),
-            config: new_gil!(CarConfig, py, info.config),
-        })
+            state: info.state.into(),
+            config: info.config.into(),
+        }
     }
 }
 
-impl RemoveGil<CCarInfo> for &CarInfo {
+impl From<CarInfo> for CCarInfo {
     #[inline]
-    fn remove_gil(self, py: Python) -> CCarInfo {
-        CCarInfo {
-            id: self.id,
-            team: self.team.into(),
-            state: self.state.borrow(py).clone().remove_gil(py),
-            config: self.config.borrow(py).clone().remove_gil(py),
+    fn from(info: CarInfo) -> Self {
+        Self {
+            id: info.id,
+            team: info.team.into(),
+            state: info.state.into(),
+            config: info.config.into(),
         }
     }
 }
@@ -44,14 +80,21 @@ impl RemoveGil<CCarInfo> for &CarInfo {
 impl CarInfo {
     #[new]
     #[inline]
-    #[pyo3(signature = (id, team=Team::Blue, state=None, config=None))]
-    fn __new__(py: Python, id: u32, team: Team, state: Option<Py<Car>>, config: Option<Py<CarConfig>>) -> PyResult<Self> {
-        Ok(Self {
-            id,
-            team,
-            state: state.unwrap_or(new_gil_default!(Car, py)),
-            config: config.unwrap_or(new_gil_default!(CarConfig, py)),
-        })
+    #[pyo3(signature = (id, team=Team::Blue, state=Car::default(), config=CarConfig::default()))]
+    fn __new__(id: u32, team: Team, state: Car, config: CarConfig) -> Self {
+        Self { id, team, state, config }
+    }
+
+    #[getter]
+    fn state(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.state;
+        CarRef::wrap(py, slf.into_py(py), "state", value)
+    }
+
+    #[getter]
+    fn config(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.config;
+        CarConfigRef::wrap(py, slf.into_py(py), "config", value)
     }
 
     #[inline]
@@ -60,44 +103,57 @@ impl CarInfo {
     }
 
     #[inline]
-    fn __repr__(&self, py: Python) -> String {
+    fn __repr__(&self) -> String {
         format!(
             "CarInfo(id={}, team={}, state={}, config={})",
             self.id,
             self.team.__repr__(),
-            self.state.borrow(py).__repr__(py),
-            self.config.borrow(py).__repr__(py)
+            self.state.__repr__(),
+            self.config.__repr__()
         )
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
 }
 
-#[pyclass(get_all, module = "rocketsim")]
-#[derive(Clone, Debug)]
+#[pyclass(module = "rocketsim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct BoostPad {
+    #[pyo3(get)]
     pub is_big: bool,
-    pub position: Py<Vec3>,
+    pub position: Vec3,
     #[pyo3(set)]
-    pub state: Py<BoostPadState>,
+    pub state: BoostPadState,
 }
 
-impl FromGil<CBoostPad> for BoostPad {
+impl PyEq for BoostPad {
     #[inline]
-    fn from_gil(py: Python, obj: CBoostPad) -> PyResult<Self> {
-        Ok(BoostPad {
-            is_big: obj.is_big,
-            position: new_gil!(Vec3, py, obj.position),
-            state: new_gil!(BoostPadState, py, obj.state),
-        })
+    fn py_eq(&self, _py: Python, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl From<CBoostPad> for BoostPad {
+    #[inline]
+    fn from(pad: CBoostPad) -> Self {
+        Self {
+            is_big: pad.is_big,
+            position: pad.position.into(),
+            state: pad.state.into(),
+        }
     }
 }
 
-impl RemoveGil<CBoostPad> for BoostPad {
+impl From<BoostPad> for CBoostPad {
     #[inline]
-    fn remove_gil(self, py: Python) -> CBoostPad {
-        CBoostPad {
-            is_big: self.is_big,
-            position: self.position.remove_gil(py),
-            state: self.state.remove_gil(py),
+    fn from(pad: BoostPad) -> Self {
+        Self {
+            is_big: pad.is_big,
+            position: pad.position.into(),
+            state: pad.state.into(),
         }
     }
 }
@@ -106,13 +162,21 @@ impl RemoveGil<CBoostPad> for BoostPad {
 impl BoostPad {
     #[new]
     #[inline]
-    #[pyo3(signature = (is_big=false, position=None, state=None))]
-    fn __new__(py: Python, is_big: bool, position: Option<Py<Vec3>>, state: Option<Py<BoostPadState>>) -> PyResult<Self> {
-        Ok(Self {
-            is_big,
-            position: position.unwrap_or(new_gil_default!(Vec3, py)),
-            state: state.unwrap_or(new_gil_default!(BoostPadState, py)),
-        })
+    #[pyo3(signature = (is_big=false, position=Vec3::ZERO, state=BoostPadState::default()))]
+    fn __new__(is_big: bool, position: Vec3, state: BoostPadState) -> Self {
+        Self { is_big, position, state }
+    }
+
+    #[getter]
+    fn position(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.position;
+        Vec3Ref::wrap(py, slf.into_py(py), "position", value)
+    }
+
+    #[getter]
+    fn state(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.state;
+        BoostPadStateRef::wrap(py, slf.into_py(py), "state", value)
     }
 
     #[inline]
@@ -121,63 +185,110 @@ impl BoostPad {
     }
 
     #[inline]
-    fn __repr__(&self, py: Python) -> String {
+    fn __repr__(&self) -> String {
         format!(
             "BoostPad(is_big={}, position={}, state={})",
             repr_bool(self.is_big),
-            self.position.borrow(py).__repr__(),
-            self.state.borrow(py).__repr__()
+            self.position.__repr__(),
+            self.state.__repr__()
         )
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self == other, op)
+    }
 }
 
-#[pyclass(get_all, set_all, module = "rocketsim")]
+#[pyclass(set_all, module = "rocketsim")]
 #[derive(Clone, Debug)]
 pub struct GameState {
+    #[pyo3(get)]
     pub tick_rate: f32,
+    #[pyo3(get)]
     pub tick_count: u64,
-    pub ball: Py<Ball>,
-    pub ball_rot: Py<RotMat>,
+    pub ball: Ball,
+    pub ball_rot: RotMat,
+    #[pyo3(get)]
     pub cars: Vec<Py<CarInfo>>,
+    #[pyo3(get)]
     pub pads: Vec<Py<BoostPad>>,
 }
 
+impl PyEq for GameState {
+    fn py_eq(&self, py: Python, other: &Self) -> bool {
+        self.tick_rate == other.tick_rate
+            && self.tick_count == other.tick_count
+            && self.ball == other.ball
+            && self.ball_rot == other.ball_rot
+            && self.cars.py_eq(py, &other.cars)
+            && self.pads.py_eq(py, &other.pads)
+    }
+}
+
+impl FromGil<CGameState> for GameState {
+    #[inline]
+    fn from_gil(py: Python, game_state: CGameState) -> PyResult<Self> {
+        Ok(Self {
+            tick_rate: game_state.tick_rate,
+            tick_count: game_state.tick_count,
+            ball: game_state.ball.into(),
+            ball_rot: Quat::from_array(game_state.ball_rot).into(),
+            cars: game_state.cars.into_iter().map(|car| Py::new(py, CarInfo::from(car))).collect::<Result<_, _>>()?,
+            pads: game_state.pads.into_iter().map(|pad| Py::new(py, BoostPad::from(pad))).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
 impl RemoveGil<CGameState> for GameState {
     #[inline]
     fn remove_gil(self, py: Python) -> CGameState {
         CGameState {
             tick_rate: self.tick_rate,
             tick_count: self.tick_count,
-            ball: self.ball.borrow(py).clone().remove_gil(py),
-            ball_rot: RemoveGil::<Quat>::remove_gil(self.ball_rot.borrow(py).clone(), py).to_array(),
-            cars: self.cars.into_iter().map(|car| car.borrow(py).remove_gil(py)).collect(),
+            ball: self.ball.into(),
+            ball_rot: Quat::from(self.ball_rot).to_array(),
+            cars: self.cars.into_iter().map(|car| (*car.borrow(py)).into()).collect(),
             pads: Vec::new(),
         }
     }
 }
 
+impl GameState {
+    /// The full game state, including the pads that `RemoveGil` leaves out
+    pub fn remove_gil_with_pads(&self, py: Python) -> CGameState {
+        let mut game_state = self.clone().remove_gil(py);
+        game_state.pads = self.pads.iter().map(|pad| (*pad.borrow(py)).into()).collect();
+        game_state
+    }
+}
+
 #[pymethods]
 impl GameState {
     #[new]
     #[inline]
-    #[pyo3(signature = (tick_count=0, tick_rate=120., ball=None, ball_rot=None, cars=Vec::new(), pads=Vec::new()))]
-    fn __new__(
-        py: Python,
-        tick_count: u64,
-        tick_rate: f32,
-        ball: Option<Py<Ball>>,
-        ball_rot: Option<Py<RotMat>>,
-        cars: Vec<Py<CarInfo>>,
-        pads: Vec<Py<BoostPad>>,
-    ) -> PyResult<Self> {
-        Ok(Self {
+    #[pyo3(signature = (tick_count=0, tick_rate=120., ball=Ball::default(), ball_rot=RotMat::ZERO, cars=Vec::new(), pads=Vec::new()))]
+    fn __new__(tick_count: u64, tick_rate: f32, ball: Ball, ball_rot: RotMat, cars: Vec<Py<CarInfo>>, pads: Vec<Py<BoostPad>>) -> Self {
+        Self {
             tick_rate,
             tick_count,
-            ball: ball.unwrap_or(new_gil_default!(Ball, py)),
-            ball_rot: ball_rot.unwrap_or(new_gil_default!(RotMat, py)),
+            ball,
+            ball_rot,
             cars,
             pads,
-        })
+        }
+    }
+
+    #[getter]
+    fn ball(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.ball;
+        BallRef::wrap(py, slf.into_py(py), "ball", value)
+    }
+
+    #[getter]
+    fn ball_rot(slf: PyRef<Self>, py: Python) -> PyResult<PyObject> {
+        let value = slf.ball_rot;
+        RotMatRef::wrap(py, slf.into_py(py), "ball_rot", value)
     }
 
     #[inline]
@@ -191,10 +302,41 @@ impl GameState {
             "GameState(tick_count={}, tick_rate={}, ball={}, ball_rot={}, cars=[{}], pads=[{}])",
             self.tick_count,
             self.tick_rate,
-            self.ball.borrow(py).__repr__(py),
-            self.ball_rot.borrow(py).__repr__(py),
-            self.cars.iter().map(|car| car.borrow(py).__repr__(py)).collect::<Vec<_>>().join(", "),
-            self.pads.iter().map(|pad| pad.borrow(py).__repr__(py)).collect::<Vec<_>>().join(", ")
+            self.ball.__repr__(),
+            self.ball_rot.__repr__(),
+            self.cars.iter().map(|car| car.borrow(py).__repr__()).collect::<Vec<_>>().join(", "),
+            self.pads.iter().map(|pad| pad.borrow(py).__repr__()).collect::<Vec<_>>().join(", ")
         )
     }
+
+    #[inline]
+    fn __richcmp__(&self, py: Python, other: &Self, op: CompareOp) -> PyObject {
+        richcmp_eq(py, self.py_eq(py, other), op)
+    }
+
+    /// The game state in the rocketsim_rs binary format, which `ArenaRecorder` and `serve` also use
+    #[inline]
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.remove_gil_with_pads(py).to_bytes())
+    }
+
+    #[staticmethod]
+    fn from_bytes(py: Python, bytes: &[u8]) -> PyResult<Self> {
+        checked_game_state_from_bytes(bytes).map_err(PyValueError::new_err)?.into_gil(py)
+    }
+
+    /// Every field of the ball, cars and pads that differs by more than the tolerance, with cars compared by their index
+    #[pyo3(signature = (other, tolerance=DEFAULT_TOLERANCE))]
+    fn diff(&self, py: Python, other: &Self, tolerance: f32) -> Vec<FieldDiff> {
+        let (old, new) = (self.remove_gil_with_pads(py), other.remove_gil_with_pads(py));
+
+        let mut differ = Differ::new(tolerance);
+        for (i, (old_car, new_car)) in old.cars.iter().zip(&new.cars).enumerate() {
+            differ.id(&format!("cars[{i}]"), "id", old_car.id, new_car.id);
+            differ.car_ids.insert(old_car.id, new_car.id);
+        }
+
+        differ.game_state(&old, &new, old.cars.iter().enumerate().map(|(i, car)| (format!("cars[{i}]"), car, new.cars.get(i))));
+        differ.diffs
+    }
 }
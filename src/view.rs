@@ -0,0 +1,202 @@
+use std::sync::Weak;
+
+use pyo3::{
+    exceptions::{PyIndexError, PyReferenceError},
+    ffi,
+    prelude::*,
+    AsPyPointer,
+};
+
+use rocketsim_rs::sim as csim;
+
+use crate::{
+    base::{RotMat, Vec3},
+    python::{Arena, Ball, BallHitInfo, Car, CarControls, RemovedCars},
+};
+
+/// A reference to an `Arena` that doesn't keep it alive
+struct ArenaRef {
+    ptr: *mut ffi::PyObject,
+    removed_cars: Weak<RemovedCars>,
+}
+
+impl ArenaRef {
+    #[inline]
+    fn new(arena: &PyCell<Arena>) -> Self {
+        Self {
+            ptr: arena.as_ptr(),
+            removed_cars: arena.borrow().removed_cars(),
+        }
+    }
+
+    /// The arena, raising a ReferenceError if it was dropped
+    fn get<'py>(&self, py: Python<'py>) -> PyResult<&'py PyCell<Arena>> {
+        if self.removed_cars.strong_count() == 0 {
+            return Err(PyReferenceError::new_err("The arena of this view was dropped"));
+        }
+
+        // SAFETY: the arena's Arc is only dropped with the arena, so the object is still alive,
+        // and it can't be dropped by another thread while this one holds the GIL
+        Ok(unsafe { py.from_borrowed_ptr(self.ptr) })
+    }
+
+    /// True if the arena is alive and the car wasn't removed from it, which doesn't need to borrow the arena
+    fn has_car(&self, id: u32) -> bool {
+        self.removed_cars.upgrade().is_some_and(|removed_cars| !removed_cars.lock().unwrap().contains(&id))
+    }
+}
+
+/// Generates a getter for each field that converts only that field of the state read by `$read`
+macro_rules! view_getters {
+    ($view:ident, $read:ident, { $($field:ident: $ty:ty),* $(,)? }, $($methods:tt)*) => {
+        #[pymethods]
+        impl $view {
+            $(
+                #[getter]
+                #[inline]
+                fn $field(&self, py: Python) -> PyResult<$ty> {
+                    self.$read(py, |state| state.$field.into())
+                }
+            )*
+
+            $($methods)*
+        }
+    };
+}
+
+/// A live view of a car, each attribute is read from the arena when it's accessed
+#[pyclass(unsendable, module = "rocketsim.sim")]
+pub struct CarView {
+    arena: ArenaRef,
+    id: u32,
+}
+
+impl CarView {
+    pub fn new(arena: &PyCell<Arena>, id: u32) -> PyResult<Self> {
+        if !arena.borrow().0.get_cars().contains(&id) {
+            return Err(PyIndexError::new_err(format!("No car with id {id}")));
+        }
+
+        Ok(Self { arena: ArenaRef::new(arena), id })
+    }
+
+    /// Reads `f` from the car's state, RocketSim can only copy out the whole state and needs the arena mutably for it
+    fn read_car<T>(&self, py: Python, f: impl FnOnce(&csim::CarState) -> T) -> PyResult<T> {
+        let arena = self.arena.get(py)?;
+
+        if !self.arena.has_car(self.id) {
+            return Err(PyIndexError::new_err(format!("No car with id {}", self.id)));
+        }
+
+        Ok(f(&arena.try_borrow_mut()?.0.pin_mut().get_car(self.id)))
+    }
+}
+
+view_getters! {
+    CarView,
+    read_car,
+    {
+        pos: Vec3,
+        rot_mat: RotMat,
+        vel: Vec3,
+        ang_vel: Vec3,
+        is_on_ground: bool,
+        has_jumped: bool,
+        has_double_jumped: bool,
+        has_flipped: bool,
+        last_rel_dodge_torque: Vec3,
+        jump_time: f32,
+        flip_time: f32,
+        is_jumping: bool,
+        air_time_since_jump: f32,
+        boost: f32,
+        time_spent_boosting: f32,
+        is_supersonic: bool,
+        supersonic_time: f32,
+        handbrake_val: f32,
+        is_auto_flipping: bool,
+        auto_flip_timer: f32,
+        auto_flip_torque_scale: f32,
+        has_contact: bool,
+        contact_normal: Vec3,
+        other_car_id: u32,
+        cooldown_timer: f32,
+        is_demoed: bool,
+        demo_respawn_timer: f32,
+        ball_hit_info: BallHitInfo,
+        last_controls: CarControls,
+    },
+
+    #[getter]
+    #[inline]
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// False once the car was removed or the arena was dropped
+    #[getter]
+    fn is_valid(&self) -> bool {
+        self.arena.has_car(self.id)
+    }
+
+    /// A copy of the car's current state
+    #[inline]
+    fn get(&self, py: Python) -> PyResult<Car> {
+        self.read_car(py, |car| (*car).into())
+    }
+
+    #[inline]
+    fn __repr__(&self) -> String {
+        format!("CarView(id={})", self.id)
+    }
+}
+
+/// A live view of the ball, each attribute is read from the arena when it's accessed
+#[pyclass(unsendable, module = "rocketsim.sim")]
+pub struct BallView {
+    arena: ArenaRef,
+}
+
+impl BallView {
+    #[inline]
+    pub fn new(arena: &PyCell<Arena>) -> Self {
+        Self { arena: ArenaRef::new(arena) }
+    }
+
+    /// Reads `f` from the ball's state, RocketSim can only copy out the whole state and needs the arena mutably for it
+    fn read_ball<T>(&self, py: Python, f: impl FnOnce(&csim::BallState) -> T) -> PyResult<T> {
+        Ok(f(&self.arena.get(py)?.try_borrow_mut()?.0.pin_mut().get_ball()))
+    }
+}
+
+view_getters! {
+    BallView,
+    read_ball,
+    {
+        pos: Vec3,
+        vel: Vec3,
+        ang_vel: Vec3,
+    },
+
+    #[getter]
+    fn rot_mat(&self, py: Python) -> PyResult<RotMat> {
+        Ok(self.arena.get(py)?.try_borrow()?.get_ball_rotation())
+    }
+
+    /// False once the arena was dropped
+    #[getter]
+    fn is_valid(&self) -> bool {
+        self.arena.removed_cars.strong_count() != 0
+    }
+
+    /// A copy of the ball's current state
+    #[inline]
+    fn get(&self, py: Python) -> PyResult<Ball> {
+        self.read_ball(py, |ball| (*ball).into())
+    }
+
+    #[inline]
+    fn __repr__(&self) -> String {
+        "BallView()".to_string()
+    }
+}